@@ -272,7 +272,7 @@ class MainActivity : ComponentActivity() {
                                 var stylePath = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFileState.value}").absolutePath
                                 if (isMixingEnabledState.value) {
                                     val stylePath2 = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFile2State.value}").absolutePath
-                                    stylePath = "$stylePath;$stylePath2;${mixAlphaState.value}"
+                                    stylePath = VoiceBlend.mix(stylePath, stylePath2, mixAlphaState.value).toStylePath()
                                 }
                                 putExtra(PlaybackActivity.EXTRA_VOICE_PATH, stylePath)
                                 
@@ -354,11 +354,10 @@ class MainActivity : ComponentActivity() {
         // Check v1 dir for default listing (assuming same names in v2)
         val voiceDir = File(filesDir, "v1/voice_styles")
         if (voiceDir.exists()) {
-            val files = voiceDir.listFiles { _, name -> name.endsWith(".json") }
-            files?.forEach { file ->
-                if (!voiceResources.containsKey(file.name)) {
-                    val friendlyName = file.name.removeSuffix(".json")
-                    voiceFiles[friendlyName] = file.name
+            SupertonicTTS.listVoiceLibrary(voiceDir.absolutePath).forEach { voice ->
+                val fileName = File(voice.path).name
+                if (!voiceResources.containsKey("${voice.id}.json")) {
+                    voiceFiles[voice.displayName] = fileName
                 }
             }
         }
@@ -431,7 +430,7 @@ class MainActivity : ComponentActivity() {
         if (isMixingEnabledState.value) {
             val stylePath2 = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFile2State.value}").absolutePath
             if (File(stylePath2).exists()) {
-                stylePath = "$stylePath;$stylePath2;${mixAlphaState.value}"
+                stylePath = VoiceBlend.mix(stylePath, stylePath2, mixAlphaState.value).toStylePath()
             }
         }
         
@@ -474,7 +473,7 @@ class MainActivity : ComponentActivity() {
         var stylePath = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFileState.value}").absolutePath
         if (isMixingEnabledState.value) {
             val stylePath2 = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFile2State.value}").absolutePath
-            stylePath = "$stylePath;$stylePath2;${mixAlphaState.value}"
+            stylePath = VoiceBlend.mix(stylePath, stylePath2, mixAlphaState.value).toStylePath()
         }
 
         try {
@@ -496,7 +495,7 @@ class MainActivity : ComponentActivity() {
         var stylePath = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFileState.value}").absolutePath
         if (isMixingEnabledState.value) {
             val stylePath2 = File(filesDir, "$currentModelVersion/voice_styles/${selectedVoiceFile2State.value}").absolutePath
-            stylePath = "$stylePath;$stylePath2;${mixAlphaState.value}"
+            stylePath = VoiceBlend.mix(stylePath, stylePath2, mixAlphaState.value).toStylePath()
         }
         launchPlaybackActivity(text, stylePath)
     }
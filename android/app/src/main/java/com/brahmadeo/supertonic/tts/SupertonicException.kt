@@ -0,0 +1,18 @@
+package com.brahmadeo.supertonic.tts
+
+/**
+ * Thrown by the native engine. [code] identifies the failure class and
+ * matches `EngineError::code` in the Rust library.
+ */
+class SupertonicException(val code: Int, message: String) : RuntimeException(message) {
+    companion object {
+        const val NOT_INITIALIZED = 1
+        const val INVALID_ARGUMENT = 2
+        const val MODEL_LOAD = 3
+        const val VOICE_STYLE = 4
+        const val SYNTHESIS = 5
+        const val CANCELLED = 6
+        const val JNI = 7
+        const val NATIVE_PANIC = 8
+    }
+}
@@ -15,9 +15,17 @@ object SupertonicTTS {
     }
 
     private external fun init(modelPath: String, libPath: String): Long
-    private external fun synthesize(ptr: Long, text: String, lang: String, stylePath: String, speed: Float, bufferSeconds: Float, steps: Int): ByteArray
+    private external fun synthesize(ptr: Long, text: String, lang: String, stylePath: String, speed: Float, bufferSeconds: Float, steps: Int, sampleRate: Int): ByteArray
     private external fun getSocClass(ptr: Long): Int
     private external fun getSampleRate(ptr: Long): Int
+    private external fun getLastWordTimings(ptr: Long): String
+    private external fun encodeLastAudio(ptr: Long, format: String): ByteArray
+    private external fun setLexicon(ptr: Long, json: String): Boolean
+    private external fun listVoices(voiceDir: String): String
+    private external fun preloadStyle(ptr: Long, stylePath: String): Boolean
+    private external fun evictStyle(ptr: Long, stylePath: String): Int
+    private external fun clearStyleCache(ptr: Long)
+    private external fun setPostProcessing(ptr: Long, enabled: Boolean)
     private external fun close(ptr: Long)
     private external fun reset(ptr: Long)
 
@@ -34,7 +42,12 @@ object SupertonicTTS {
             }
         }
         
-        nativePtr = init(modelPath, libPath)
+        nativePtr = try {
+            init(modelPath, libPath)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Engine initialization error ${e.code}: ${e.message}")
+            0L
+        }
         val success = nativePtr != 0L
         if (success) {
             Log.i("SupertonicTTS", "Engine initialized successfully: $nativePtr")
@@ -101,8 +114,12 @@ object SupertonicTTS {
         return isCancelled
     }
 
+    /**
+     * Synthesize [text] as 16-bit mono PCM. [sampleRate] resamples the output
+     * (including streamed chunks) in the engine; 0 keeps [getAudioSampleRate].
+     */
     @Synchronized
-    fun generateAudio(text: String, lang: String, stylePath: String, speed: Float = 1.0f, bufferDuration: Float = 0.0f, steps: Int = 5, listener: ProgressListener? = null): ByteArray? {
+    fun generateAudio(text: String, lang: String, stylePath: String, speed: Float = 1.0f, bufferDuration: Float = 0.0f, steps: Int = 5, listener: ProgressListener? = null, sampleRate: Int = 0): ByteArray? {
         if (nativePtr == 0L) {
             Log.e("SupertonicTTS", "Engine not initialized")
             return null
@@ -112,8 +129,15 @@ object SupertonicTTS {
         currentTaskListener = listener
         
         try {
-            val data = synthesize(nativePtr, text, lang, stylePath, speed, bufferDuration, steps)
+            val data = synthesize(nativePtr, text, lang, stylePath, speed, bufferDuration, steps, sampleRate)
             return if (data.isNotEmpty()) data else null
+        } catch (e: SupertonicException) {
+            if (e.code == SupertonicException.CANCELLED) {
+                Log.i("SupertonicTTS", "Native synthesis cancelled")
+            } else {
+                Log.e("SupertonicTTS", "Native synthesis error ${e.code}: ${e.message}")
+            }
+            return null
         } catch (e: Exception) {
             Log.e("SupertonicTTS", "Native synthesis exception: ${e.message}")
             return null
@@ -134,6 +158,116 @@ object SupertonicTTS {
         return getSampleRate(nativePtr)
     }
 
+    /**
+     * Word timings of the last generated audio as a JSON array of
+     * {word, char_start, char_end, start, end}; offsets refer to the text passed
+     * to generateAudio, times are in seconds.
+     */
+    @Synchronized
+    fun getLastWordTimingsJson(): String {
+        if (nativePtr == 0L) return "[]"
+        return getLastWordTimings(nativePtr)
+    }
+
+    /**
+     * The last generated audio as a complete file in [format]: "wav", "wav24",
+     * "wav32f", "flac", "flac24" or "opus[:kbps]" (Opus only if the native
+     * library was built with it). Returns null on failure.
+     */
+    @Synchronized
+    fun encodeLastAudioAs(format: String): ByteArray? {
+        if (nativePtr == 0L) return null
+        return try {
+            encodeLastAudio(nativePtr, format)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to encode audio as $format: ${e.message}")
+            null
+        }
+    }
+
+    /**
+     * Voices (style files plus optional metadata) in [voiceDir]. Works without
+     * an initialized engine; returns an empty list if the directory is unreadable.
+     */
+    fun listVoiceLibrary(voiceDir: String): List<VoiceInfo> {
+        return try {
+            VoiceInfo.parseList(listVoices(voiceDir))
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to list voices: ${e.message}")
+            emptyList()
+        } catch (e: UnsatisfiedLinkError) {
+            Log.e("SupertonicTTS", "Native library not loaded: ${e.message}")
+            emptyList()
+        }
+    }
+
+    /**
+     * Replace the engine-side pronunciation lexicon. Takes the same JSON array
+     * LexiconManager stores in user_lexicon.json; an empty string clears it.
+     * Returns false if the JSON or one of its regex terms is invalid.
+     */
+    @Synchronized
+    fun setLexiconJson(json: String): Boolean {
+        if (nativePtr == 0L) return false
+        return try {
+            setLexicon(nativePtr, json)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to set lexicon: ${e.message}")
+            false
+        }
+    }
+
+    /**
+     * Parse a voice style (a path or a [VoiceBlend] recipe) into the
+     * engine's style cache so the first sentence does not pay for loading it.
+     */
+    @Synchronized
+    fun preloadVoiceStyle(stylePath: String): Boolean {
+        if (nativePtr == 0L) return false
+        return try {
+            preloadStyle(nativePtr, stylePath)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to preload voice style: ${e.message}")
+            false
+        }
+    }
+
+    /**
+     * Drop a cached voice style, e.g. after deleting or replacing its file.
+     * Evicting a single path also drops blends that use it.
+     */
+    @Synchronized
+    fun evictVoiceStyle(stylePath: String): Int {
+        if (nativePtr == 0L) return 0
+        return try {
+            evictStyle(nativePtr, stylePath)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to evict voice style: ${e.message}")
+            0
+        }
+    }
+
+    @Synchronized
+    fun clearVoiceStyleCache() {
+        if (nativePtr != 0L) {
+            clearStyleCache(nativePtr)
+        }
+    }
+
+    /**
+     * Trim silence, normalize loudness and smooth the joins between sentences
+     * in the following [generateAudio] calls. Off by default.
+     */
+    @Synchronized
+    fun setPostProcessingEnabled(enabled: Boolean) {
+        if (nativePtr == 0L) return
+        try {
+            setPostProcessing(nativePtr, enabled)
+        } catch (e: SupertonicException) {
+            Log.e("SupertonicTTS", "Failed to set post-processing: ${e.message}")
+        }
+    }
+
     @Synchronized
     fun release() {
         if (nativePtr != 0L) {
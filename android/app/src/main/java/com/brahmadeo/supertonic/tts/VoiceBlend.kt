@@ -0,0 +1,38 @@
+package com.brahmadeo.supertonic.tts
+
+import org.json.JSONArray
+import org.json.JSONObject
+
+/**
+ * Weighted blend of voice styles, passed to the engine wherever a style path
+ * is accepted. Weights are normalized by the engine; [Voice.ttlWeight]
+ * (timbre) and [Voice.dpWeight] (pacing) override [Voice.weight] per component.
+ */
+data class VoiceBlend(val voices: List<Voice>) {
+    data class Voice(
+        val path: String,
+        val weight: Float = 1.0f,
+        val ttlWeight: Float? = null,
+        val dpWeight: Float? = null
+    )
+
+    /** JSON recipe understood by the native style cache */
+    fun toStylePath(): String {
+        val array = JSONArray()
+        for (voice in voices) {
+            val obj = JSONObject()
+            obj.put("path", voice.path)
+            obj.put("weight", voice.weight.toDouble())
+            voice.ttlWeight?.let { obj.put("ttlWeight", it.toDouble()) }
+            voice.dpWeight?.let { obj.put("dpWeight", it.toDouble()) }
+            array.put(obj)
+        }
+        return array.toString()
+    }
+
+    companion object {
+        /** Two-voice mix: [alpha] is the share of [path2] */
+        fun mix(path1: String, path2: String, alpha: Float): VoiceBlend =
+            VoiceBlend(listOf(Voice(path1, 1.0f - alpha), Voice(path2, alpha)))
+    }
+}
@@ -0,0 +1,39 @@
+package com.brahmadeo.supertonic.tts
+
+import org.json.JSONObject
+
+/**
+ * A voice found by the native voice registry (see `voices.rs`). [path] is
+ * the style file to pass as a style path; the metadata fields are optional.
+ */
+data class VoiceInfo(
+    val id: String,
+    val path: String,
+    val name: String?,
+    val gender: String?,
+    val languages: List<String>,
+    val description: String?,
+    val previewText: String?
+) {
+    val displayName: String get() = name ?: id
+
+    companion object {
+        /** Parse the `{"default": id, "voices": [...]}` JSON from `listVoices` */
+        fun parseList(json: String): List<VoiceInfo> {
+            val voices = JSONObject(json).optJSONArray("voices") ?: return emptyList()
+            return (0 until voices.length()).map { i ->
+                val obj = voices.getJSONObject(i)
+                val langs = obj.optJSONArray("languages")
+                VoiceInfo(
+                    id = obj.getString("id"),
+                    path = obj.getString("path"),
+                    name = obj.optString("name").ifEmpty { null },
+                    gender = obj.optString("gender").ifEmpty { null },
+                    languages = (0 until (langs?.length() ?: 0)).map { langs!!.getString(it) },
+                    description = obj.optString("description").ifEmpty { null },
+                    previewText = obj.optString("previewText").ifEmpty { null }
+                )
+            }
+        }
+    }
+}
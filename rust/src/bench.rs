@@ -0,0 +1,366 @@
+// ============================================================================
+// Benchmark - time per pipeline stage across texts, step counts and threads
+// ============================================================================
+//
+// Every combination of text, denoising steps and thread count is a case. A
+// case runs `warmup` untimed syntheses, then `runs` timed ones; each timed
+// run records the wall-clock time of the whole synthesis and of every model
+// (`StageTimings`). RTF is processing time over audio duration, so lower is
+// faster and a stage's RTF is its share of the total. Runs that produce no
+// audio have no RTF and are left out of the RTF statistics.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use supertonic_tts::helper::{EngineOptions, StageTimings, Style, SynthesisOptions, TextToSpeech};
+
+/// Texts used when none are given: a sentence, a paragraph and a page
+pub const DEFAULT_TEXTS: [&str; 3] = [
+    "The quick brown fox jumps over the lazy dog.",
+    "This morning, I took a walk in the park, and the sound of the birds and the breeze was so pleasant that I stopped for a long time just to listen. The path wound past a small pond where ducks drifted lazily in the sun.",
+    "This morning, I took a walk in the park, and the sound of the birds and the breeze was so pleasant that I stopped for a long time just to listen. The path wound past a small pond where ducks drifted lazily in the sun. \
+     On the far side, an old man was feeding pigeons from a paper bag, and children were chasing each other around the fountain. \
+     I sat down on a bench under a large oak tree and watched the clouds move slowly across the sky.\n\n\
+     By noon the park had filled with people. Office workers ate their lunches on the grass, joggers wove between strollers, and a street musician played a gentle melody on his violin. \
+     I realized that I had not taken a moment like this in months, and I promised myself that I would come back more often.",
+];
+
+/// What to measure
+pub struct BenchConfig<'a> {
+    pub texts: &'a [String],
+    pub lang: &'a str,
+    pub style: &'a Style,
+    pub options: &'a SynthesisOptions,
+    pub steps: &'a [usize],
+    /// Intra-op thread counts; `None` keeps the engine's setting
+    pub threads: &'a [Option<usize>],
+    pub runs: usize,
+    pub warmup: usize,
+}
+
+/// One timed synthesis
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRecord {
+    /// Index into the benchmarked texts
+    pub text: usize,
+    pub text_chars: usize,
+    pub steps: usize,
+    pub threads: Option<usize>,
+    pub run: usize,
+    pub audio_seconds: f64,
+    pub total_seconds: f64,
+    /// `None` when the run produced no audio
+    pub rtf: Option<f64>,
+    pub stages: StageTimings,
+    /// Time outside the models: text processing, noise, joining, resampling
+    pub other_seconds: f64,
+}
+
+/// Means over the timed runs of one case
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaseSummary {
+    pub text: usize,
+    pub text_chars: usize,
+    pub steps: usize,
+    pub threads: Option<usize>,
+    pub runs: usize,
+    /// Runs that produced no audio and are left out of `rtf`
+    pub silent_runs: usize,
+    pub audio_seconds: f64,
+    pub total_seconds: f64,
+    /// Mean time of a single denoising step (seconds)
+    pub vector_estimator_step_seconds: f64,
+    /// `None` when no run produced audio
+    #[serde(flatten)]
+    pub rtf: Option<RtfSummary>,
+}
+
+/// RTF statistics over the runs of a case that produced audio
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RtfSummary {
+    pub rtf: f64,
+    pub rtf_min: f64,
+    pub rtf_max: f64,
+    pub duration_predictor_rtf: f64,
+    pub text_encoder_rtf: f64,
+    pub vector_estimator_rtf: f64,
+    pub vocoder_rtf: f64,
+    pub other_rtf: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchReport {
+    /// Free-form tag, e.g. the model version
+    pub label: Option<String>,
+    pub onnx_dir: String,
+    pub sample_rate: i32,
+    pub lang: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub warmup: usize,
+    pub summary: Vec<CaseSummary>,
+    pub runs: Vec<RunRecord>,
+}
+
+/// `seconds` per second of audio, or `None` when no audio came out
+fn per_audio_second(seconds: f64, audio_seconds: f64) -> Option<f64> {
+    (audio_seconds > 0.0).then(|| seconds / audio_seconds)
+}
+
+/// Run every case, reporting each timed run on stderr. A `None` thread count
+/// runs on the engine's own setting, which is restored afterwards.
+pub fn run_benchmark(tts: &mut TextToSpeech, config: &BenchConfig) -> Result<Vec<RunRecord>> {
+    let original = tts.engine_options().clone();
+    let records = run_cases(tts, config, &original);
+    tts.set_engine_options(&original)?;
+    records
+}
+
+fn run_cases(tts: &mut TextToSpeech, config: &BenchConfig, original: &EngineOptions) -> Result<Vec<RunRecord>> {
+    let mut records = Vec::new();
+    for &threads in config.threads {
+        let mut options = original.clone();
+        if let Some(n) = threads {
+            options.session.intra_threads = Some(n.max(1));
+        }
+        tts.set_engine_options(&options)?;
+        for &steps in config.steps {
+            let options = SynthesisOptions { total_step: steps, ..config.options.clone() };
+            let sample_rate = tts.output_sample_rate(&options);
+            for (text_index, text) in config.texts.iter().enumerate() {
+                for _ in 0..config.warmup {
+                    tts.call_with_timing(text, config.lang, config.style, &options, |_, _, _| true)?;
+                }
+                for run in 0..config.runs {
+                    tts.take_stage_timings();
+                    let start = Instant::now();
+                    let (wav, _, _) = tts.call_with_timing(text, config.lang, config.style, &options, |_, _, _| true)?;
+                    let total_seconds = start.elapsed().as_secs_f64();
+                    let stages = tts.take_stage_timings();
+
+                    let audio_seconds = wav.len() as f64 / sample_rate as f64;
+                    let record = RunRecord {
+                        text: text_index,
+                        text_chars: text.chars().count(),
+                        steps,
+                        threads,
+                        run: run + 1,
+                        audio_seconds,
+                        total_seconds,
+                        rtf: per_audio_second(total_seconds, audio_seconds),
+                        other_seconds: (total_seconds - stages.total()).max(0.0),
+                        stages,
+                    };
+                    eprintln!(
+                        "text {} ({} chars), {} steps, threads {}: run {} took {:.3} s for {:.2} s of audio (RTF {})",
+                        text_index,
+                        record.text_chars,
+                        steps,
+                        threads.map_or("default".to_string(), |n| n.to_string()),
+                        record.run,
+                        total_seconds,
+                        audio_seconds,
+                        record.rtf.map_or("n/a".to_string(), |rtf| format!("{:.4}", rtf))
+                    );
+                    records.push(record);
+                }
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Group runs by case (in the order the cases ran) and average them
+pub fn summarize(records: &[RunRecord]) -> Vec<CaseSummary> {
+    let mut cases: Vec<Vec<&RunRecord>> = Vec::new();
+    for record in records {
+        let same_case = |r: &&RunRecord| r.text == record.text && r.steps == record.steps && r.threads == record.threads;
+        match cases.iter_mut().find(|case| same_case(&case[0])) {
+            Some(case) => case.push(record),
+            None => cases.push(vec![record]),
+        }
+    }
+
+    cases
+        .iter()
+        .map(|runs| {
+            let n = runs.len() as f64;
+            let mean = |f: &dyn Fn(&RunRecord) -> f64| runs.iter().map(|r| f(r)).sum::<f64>() / n;
+            let voiced: Vec<&RunRecord> = runs.iter().copied().filter(|r| r.rtf.is_some()).collect();
+            let rtf = (!voiced.is_empty()).then(|| {
+                let rtfs: Vec<f64> = voiced.iter().filter_map(|r| r.rtf).collect();
+                // Stage RTFs are averaged per run so they add up to the mean RTF
+                let stage_rtf = |f: &dyn Fn(&RunRecord) -> f64| {
+                    voiced
+                        .iter()
+                        .filter_map(|r| per_audio_second(f(r), r.audio_seconds))
+                        .sum::<f64>()
+                        / voiced.len() as f64
+                };
+                RtfSummary {
+                    rtf: rtfs.iter().sum::<f64>() / rtfs.len() as f64,
+                    rtf_min: rtfs.iter().copied().fold(f64::INFINITY, f64::min),
+                    rtf_max: rtfs.iter().copied().fold(0.0, f64::max),
+                    duration_predictor_rtf: stage_rtf(&|r| r.stages.duration_predictor),
+                    text_encoder_rtf: stage_rtf(&|r| r.stages.text_encoder),
+                    vector_estimator_rtf: stage_rtf(&|r| r.stages.vector_estimator_total()),
+                    vocoder_rtf: stage_rtf(&|r| r.stages.vocoder),
+                    other_rtf: stage_rtf(&|r| r.other_seconds),
+                }
+            });
+            let first = runs[0];
+            CaseSummary {
+                text: first.text,
+                text_chars: first.text_chars,
+                steps: first.steps,
+                threads: first.threads,
+                runs: runs.len(),
+                silent_runs: runs.len() - voiced.len(),
+                audio_seconds: mean(&|r| r.audio_seconds),
+                total_seconds: mean(&|r| r.total_seconds),
+                vector_estimator_step_seconds: mean(&|r| {
+                    r.stages.vector_estimator_total() / r.stages.vector_estimator.len().max(1) as f64
+                }),
+                rtf,
+            }
+        })
+        .collect()
+}
+
+/// Human-readable summary table
+pub fn print_summary(out: &mut impl Write, summary: &[CaseSummary]) -> io::Result<()> {
+    writeln!(
+        out,
+        "{:>4} {:>6} {:>5} {:>7} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8} {:>8}",
+        "text", "chars", "steps", "threads", "audio s", "RTF", "dp", "text_enc", "vec_est", "vocoder", "other"
+    )?;
+    for case in summary {
+        write!(
+            out,
+            "{:>4} {:>6} {:>5} {:>7} {:>8.2}",
+            case.text,
+            case.text_chars,
+            case.steps,
+            case.threads.map_or("-".to_string(), |n| n.to_string()),
+            case.audio_seconds,
+        )?;
+        match &case.rtf {
+            Some(rtf) => write!(
+                out,
+                " {:>8.4} {:>8.4} {:>8.4} {:>8.4} {:>8.4} {:>8.4}",
+                rtf.rtf,
+                rtf.duration_predictor_rtf,
+                rtf.text_encoder_rtf,
+                rtf.vector_estimator_rtf,
+                rtf.vocoder_rtf,
+                rtf.other_rtf
+            )?,
+            None => write!(out, " {:>8}", "silent")?,
+        }
+        if case.silent_runs > 0 && case.rtf.is_some() {
+            write!(out, "  ({} of {} runs silent)", case.silent_runs, case.runs)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn timestamp() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Write `contents` to `path`, or stdout for "-"
+fn write_output(path: &str, contents: &str) -> Result<()> {
+    if path == "-" {
+        io::stdout().lock().write_all(contents.as_bytes())?;
+        return Ok(());
+    }
+    fs::write(path, contents).with_context(|| format!("Failed to write {}", path))
+}
+
+pub fn write_json(report: &BenchReport, path: &str) -> Result<()> {
+    write_output(path, &(serde_json::to_string_pretty(report)? + "\n"))
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// One row per timed run. Per-step times are in the JSON report only, since
+/// the number of steps differs between cases.
+pub fn write_csv(report: &BenchReport, path: &str) -> Result<()> {
+    let mut csv = String::from(
+        "label,onnx_dir,text,text_chars,steps,threads,run,audio_seconds,total_seconds,rtf,\
+         duration_predictor_seconds,text_encoder_seconds,vector_estimator_seconds,vocoder_seconds,other_seconds\n",
+    );
+    let label = csv_field(report.label.as_deref().unwrap_or(""));
+    let onnx_dir = csv_field(&report.onnx_dir);
+    for r in &report.runs {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:.4},{:.6},{},{:.6},{:.6},{:.6},{:.6},{:.6}\n",
+            label,
+            onnx_dir,
+            r.text,
+            r.text_chars,
+            r.steps,
+            r.threads.map_or(String::new(), |n| n.to_string()),
+            r.run,
+            r.audio_seconds,
+            r.total_seconds,
+            r.rtf.map_or(String::new(), |rtf| format!("{:.6}", rtf)),
+            r.stages.duration_predictor,
+            r.stages.text_encoder,
+            r.stages.vector_estimator_total(),
+            r.stages.vocoder,
+            r.other_seconds
+        ));
+    }
+    write_output(path, &csv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(run: usize, audio_seconds: f64, total_seconds: f64) -> RunRecord {
+        RunRecord {
+            text: 0,
+            text_chars: 1,
+            steps: 5,
+            threads: None,
+            run,
+            audio_seconds,
+            total_seconds,
+            rtf: per_audio_second(total_seconds, audio_seconds),
+            stages: StageTimings { vocoder: total_seconds / 2.0, ..Default::default() },
+            other_seconds: total_seconds / 2.0,
+        }
+    }
+
+    #[test]
+    fn silent_runs_are_left_out_of_the_rtf() {
+        let summary = summarize(&[record(1, 0.0, 0.2), record(2, 2.0, 1.0), record(3, 2.0, 2.0)]);
+        assert_eq!(summary.len(), 1);
+        let case = &summary[0];
+        assert_eq!((case.runs, case.silent_runs), (3, 1));
+        let rtf = case.rtf.as_ref().unwrap();
+        assert_eq!((rtf.rtf, rtf.rtf_min, rtf.rtf_max), (0.75, 0.5, 1.0));
+        assert_eq!((rtf.vocoder_rtf, rtf.other_rtf), (0.375, 0.375));
+
+        // A case with no audio at all has no RTF rather than a perfect one
+        let silent = summarize(&[record(1, 0.0, 0.2)]);
+        assert_eq!(silent[0].silent_runs, 1);
+        assert!(silent[0].rtf.is_none());
+    }
+}
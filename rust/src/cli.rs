@@ -0,0 +1,659 @@
+// ============================================================================
+// supertonic - command line synthesis
+// ============================================================================
+//
+//   supertonic synth "Hello there." -o hello.wav
+//   supertonic synth -i chapter.txt --voice F1 -o - | aplay
+//   supertonic voices --lang ko
+//   supertonic bench --steps 2,5,10 --thread-counts 1,4 --json report.json
+//   supertonic convert-style assets/voice_styles/*.json
+//
+// Progress goes to stderr so audio can be written to stdout.
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+mod bench;
+mod subtitle;
+
+use bench::{print_summary, run_benchmark, summarize, write_csv, write_json, BenchConfig, BenchReport, DEFAULT_TEXTS};
+use subtitle::{build_cues, write_srt, write_vtt, DEFAULT_MAX_CUE_CHARS};
+use supertonic_tts::encoder::{encode_audio, write_audio_file, AudioEncoding};
+use supertonic_tts::helper::{
+    convert_voice_styles, load_engine_options, load_text_to_speech_with_options, load_voice_style,
+    sanitize_filename, timer, ChunkTiming, EngineOptions, Style, StyleDtype, SynthesisOptions, TextToSpeech,
+    STYLE_BINARY_MAGIC,
+};
+use supertonic_tts::lexicon::Lexicon;
+use supertonic_tts::postprocess::{PostProcess, TrimOptions};
+use supertonic_tts::ssml::is_ssml;
+use supertonic_tts::timing::WordTiming;
+use supertonic_tts::voices::VoiceRegistry;
+
+#[derive(Parser, Debug)]
+#[command(name = "supertonic")]
+#[command(about = "Supertonic text-to-speech with ONNX Runtime", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Synthesize speech to an audio file or stdout
+    Synth(SynthArgs),
+    /// List the voices in a voice directory
+    Voices(VoicesArgs),
+    /// Measure synthesis speed
+    Bench(BenchArgs),
+    /// Convert JSON voice styles to the compact binary format (.style)
+    ConvertStyle(ConvertStyleArgs),
+    /// Print the format and dimensions of voice style files
+    StyleInfo(StyleInfoArgs),
+}
+
+/// Where the models come from and how they run
+#[derive(Args, Debug)]
+struct EngineArgs {
+    /// Path to ONNX model directory
+    #[arg(long, default_value = "assets/onnx")]
+    onnx_dir: String,
+
+    /// Use GPU for inference (default: CPU)
+    #[arg(long, default_value = "false")]
+    use_gpu: bool,
+
+    /// ONNX Runtime intra-op threads for every model
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Engine options JSON (per-model session settings); --threads overrides its thread count
+    #[arg(long)]
+    engine_options: Option<String>,
+
+    /// Pronunciation lexicon (JSON array of {term, replacement, ...})
+    #[arg(long)]
+    lexicon: Option<String>,
+}
+
+impl EngineArgs {
+    fn engine_options(&self) -> Result<EngineOptions> {
+        let mut options = match &self.engine_options {
+            Some(path) => load_engine_options(path)?,
+            None => EngineOptions::default(),
+        };
+        if let Some(threads) = self.threads {
+            options.session.intra_threads = Some(threads);
+        }
+        Ok(options)
+    }
+
+    fn load(&self) -> Result<TextToSpeech> {
+        let mut tts = load_text_to_speech_with_options(&self.onnx_dir, self.use_gpu, &self.engine_options()?)?;
+        if let Some(path) = &self.lexicon {
+            let lexicon = Lexicon::load(path)?;
+            eprintln!("Loaded {} lexicon entries from {} ({} invalid skipped)", lexicon.len(), path, lexicon.skipped());
+            tts.set_lexicon(lexicon);
+        }
+        Ok(tts)
+    }
+}
+
+/// Which voice(s) to speak with
+#[derive(Args, Debug)]
+struct VoiceArgs {
+    /// Voice id(s) from --voice-dir
+    #[arg(long, value_delimiter = ',')]
+    voice: Vec<String>,
+
+    /// Voice style file path(s), used instead of --voice
+    #[arg(long, value_delimiter = ',', conflicts_with = "voice")]
+    voice_style: Vec<String>,
+
+    /// Directory of voice styles (and optional metadata)
+    #[arg(long, default_value = "assets/voice_styles")]
+    voice_dir: String,
+}
+
+impl VoiceArgs {
+    /// Style files for the requested voices; the default voice if none are given
+    fn style_paths(&self) -> Result<Vec<String>> {
+        if !self.voice_style.is_empty() {
+            return Ok(self.voice_style.clone());
+        }
+        let registry = VoiceRegistry::scan(&self.voice_dir)?;
+        if self.voice.is_empty() {
+            let voice = registry
+                .default_voice()
+                .ok_or_else(|| anyhow::anyhow!("No voice styles found in {}", self.voice_dir))?;
+            return Ok(vec![voice.path.to_string_lossy().into_owned()]);
+        }
+        self.voice
+            .iter()
+            .map(|id| {
+                registry
+                    .get(id)
+                    .map(|v| v.path.to_string_lossy().into_owned())
+                    .ok_or_else(|| anyhow::anyhow!("Unknown voice '{}' in {}", id, self.voice_dir))
+            })
+            .collect()
+    }
+}
+
+/// Text to synthesize
+#[derive(Args, Debug)]
+struct InputArgs {
+    /// Text to synthesize (read from stdin when omitted and stdin is not a terminal)
+    text: Vec<String>,
+
+    /// Read the text from a file ("-" for stdin)
+    #[arg(short, long, conflicts_with = "text")]
+    input: Option<String>,
+}
+
+impl InputArgs {
+    fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.input.is_none()
+    }
+
+    /// The texts to synthesize. In batch mode every argument, or every
+    /// non-empty line of the input, is a separate text; otherwise everything
+    /// is one text.
+    fn read(&self, batch: bool) -> Result<Vec<String>> {
+        let content = match (&self.input, self.text.is_empty()) {
+            (Some(path), _) if path != "-" => {
+                fs::read_to_string(path).with_context(|| format!("Failed to read {}", path))?
+            }
+            (Some(_), _) => read_stdin()?,
+            (None, false) if batch => return Ok(self.text.clone()),
+            (None, false) => return Ok(vec![self.text.join(" ")]),
+            (None, true) if !io::stdin().is_terminal() => read_stdin()?,
+            (None, true) => anyhow::bail!("No text given (pass it as arguments, with --input, or on stdin)"),
+        };
+        let texts: Vec<String> = if batch {
+            content.lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect()
+        } else {
+            vec![content]
+        };
+        if texts.iter().all(|t| t.trim().is_empty()) {
+            anyhow::bail!("Input text is empty");
+        }
+        Ok(texts)
+    }
+}
+
+fn read_stdin() -> Result<String> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text).context("Failed to read stdin")?;
+    Ok(text)
+}
+
+/// Synthesis settings shared by `synth` and `bench`
+#[derive(Args, Debug)]
+struct SynthesisArgs {
+    /// Language(s) for synthesis (en, ko, es, pt, fr)
+    #[arg(long, value_delimiter = ',', default_values_t = vec!["en".to_string()])]
+    lang: Vec<String>,
+
+    /// Number of denoising steps
+    #[arg(long, default_value = "5")]
+    total_step: usize,
+
+    /// Speech speed factor (higher = faster)
+    #[arg(long, default_value = "1.05")]
+    speed: f32,
+
+    /// Seed for reproducible output
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Synthesize this many chunks of a long text per model run (padded batch)
+    #[arg(long, default_value = "1")]
+    chunk_batch: usize,
+
+    /// Output sample rate in Hz (default: the model's rate)
+    #[arg(long)]
+    sample_rate: Option<u32>,
+
+    /// Pause after a paragraph (seconds)
+    #[arg(long)]
+    paragraph_pause: Option<f32>,
+
+    /// Pause after a sentence (seconds)
+    #[arg(long)]
+    sentence_pause: Option<f32>,
+
+    /// Pause after a comma split inside a long sentence (seconds)
+    #[arg(long)]
+    clause_pause: Option<f32>,
+
+    /// Crossfade between chunks joined without a pause (seconds)
+    #[arg(long)]
+    crossfade: Option<f32>,
+}
+
+impl SynthesisArgs {
+    fn options(&self) -> SynthesisOptions {
+        let mut options = SynthesisOptions {
+            total_step: self.total_step,
+            speed: self.speed,
+            seed: self.seed,
+            output_sample_rate: self.sample_rate,
+            chunk_batch_size: self.chunk_batch,
+            ..Default::default()
+        };
+        let join = &mut options.join;
+        join.paragraph_pause = self.paragraph_pause.unwrap_or(join.paragraph_pause);
+        join.sentence_pause = self.sentence_pause.unwrap_or(join.sentence_pause);
+        join.clause_pause = self.clause_pause.unwrap_or(join.clause_pause);
+        join.crossfade = self.crossfade.unwrap_or(join.crossfade);
+        options
+    }
+}
+
+#[derive(Args, Debug)]
+struct SynthArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    engine: EngineArgs,
+
+    #[command(flatten)]
+    voice: VoiceArgs,
+
+    #[command(flatten)]
+    synthesis: SynthesisArgs,
+
+    /// Output file ("-" for stdout); default: a name derived from the text in --out-dir
+    #[arg(short, long, conflicts_with = "batch")]
+    output: Option<String>,
+
+    /// Directory for outputs without --output
+    #[arg(long, default_value = "results")]
+    out_dir: String,
+
+    /// Output format: wav, wav24, wav32f, flac, flac24 or opus[:kbps] (default: from the --output extension, else wav)
+    #[arg(long)]
+    format: Option<AudioEncoding>,
+
+    /// Synthesize several text-voice-language triples in one batch (no chunking)
+    #[arg(long, default_value = "false")]
+    batch: bool,
+
+    /// Trim leading/trailing silence from the output
+    #[arg(long, default_value = "false")]
+    trim: bool,
+
+    /// Normalize output loudness to this level (LUFS, e.g. -18)
+    #[arg(long)]
+    target_lufs: Option<f32>,
+
+    /// Limit output peaks to this level (dBFS, e.g. -1)
+    #[arg(long)]
+    peak_ceiling: Option<f32>,
+
+    /// Apply --trim/--target-lufs/--peak-ceiling to every chunk instead of the whole output
+    #[arg(long, default_value = "false")]
+    postprocess_chunks: bool,
+
+    /// Caption formats to write next to the output (srt, vtt); not for batch, SSML or stdout output
+    #[arg(long, value_delimiter = ',')]
+    subtitles: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+struct VoicesArgs {
+    /// Directory of voice styles (and optional metadata)
+    #[arg(long, default_value = "assets/voice_styles")]
+    voice_dir: String,
+
+    /// Only voices suited to this language
+    #[arg(long)]
+    lang: Option<String>,
+
+    /// Print the voice library as JSON
+    #[arg(long, default_value = "false")]
+    json: bool,
+}
+
+#[derive(Args, Debug)]
+struct BenchArgs {
+    #[command(flatten)]
+    input: InputArgs,
+
+    #[command(flatten)]
+    engine: EngineArgs,
+
+    #[command(flatten)]
+    voice: VoiceArgs,
+
+    #[command(flatten)]
+    synthesis: SynthesisArgs,
+
+    /// Timed runs per case
+    #[arg(long, default_value = "4")]
+    runs: usize,
+
+    /// Untimed runs before each case
+    #[arg(long, default_value = "1")]
+    warmup: usize,
+
+    /// Denoising step counts to compare (default: --total-step)
+    #[arg(long, value_delimiter = ',')]
+    steps: Vec<usize>,
+
+    /// Intra-op thread counts to compare (default: --threads)
+    #[arg(long, value_delimiter = ',')]
+    thread_counts: Vec<usize>,
+
+    /// Tag stored in the reports, e.g. the model version
+    #[arg(long)]
+    label: Option<String>,
+
+    /// Write a JSON report with per-step timings ("-" for stdout)
+    #[arg(long)]
+    json: Option<String>,
+
+    /// Write a CSV report with one row per run ("-" for stdout)
+    #[arg(long)]
+    csv: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct ConvertStyleArgs {
+    /// Voice style files to convert
+    #[arg(required = true)]
+    inputs: Vec<String>,
+
+    /// Output directory (default: next to each input)
+    #[arg(long)]
+    out_dir: Option<String>,
+
+    /// Store the payload as f16 instead of f32 (half the size, lossy)
+    #[arg(long, default_value = "false")]
+    f16: bool,
+}
+
+#[derive(Args, Debug)]
+struct StyleInfoArgs {
+    /// Voice style files to inspect
+    #[arg(required = true)]
+    inputs: Vec<String>,
+}
+
+/// Move chunk and word timings by `offset` seconds (clamped at 0)
+fn shift_timings(timings: &[ChunkTiming], offset: f32) -> Vec<ChunkTiming> {
+    let shift = |t: f32| (t + offset).max(0.0);
+    timings
+        .iter()
+        .map(|chunk| ChunkTiming {
+            text: chunk.text.clone(),
+            start: shift(chunk.start),
+            end: shift(chunk.end),
+            words: chunk
+                .words
+                .iter()
+                .map(|w| WordTiming { start: shift(w.start), end: shift(w.end), ..w.clone() })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Synthesize SSML; `<voice name="F1">` refers to voice F1 next to `style_path`
+fn synthesize_ssml(
+    tts: &mut TextToSpeech,
+    ssml: &str,
+    lang: &str,
+    style: &Style,
+    style_path: &str,
+    options: &SynthesisOptions,
+) -> Result<(Vec<f32>, f32)> {
+    let voice_dir = Path::new(style_path).parent().map(Path::to_path_buf).unwrap_or_default();
+    let ssml_voices = VoiceRegistry::scan(&voice_dir).unwrap_or_default();
+    let resolve_voice = |name: &str| {
+        let path = match ssml_voices.get(name) {
+            Some(voice) => voice.path.clone(),
+            None => voice_dir.join(if name.ends_with(".json") { name.to_string() } else { format!("{}.json", name) }),
+        };
+        load_voice_style(&[path.to_string_lossy().into_owned()], false)
+    };
+    tts.call_ssml(ssml, lang, style, options, resolve_voice, |_, _, _| true)
+}
+
+fn synth(args: SynthArgs) -> Result<()> {
+    let texts = args.input.read(args.batch)?;
+    let langs = &args.synthesis.lang;
+    let mut options = args.synthesis.options();
+    let to_stdout = args.output.as_deref() == Some("-");
+
+    let format = match (args.format, &args.output) {
+        (Some(format), _) => format,
+        (None, Some(output)) if !to_stdout => match Path::new(output).extension() {
+            Some(ext) => ext
+                .to_string_lossy()
+                .parse()
+                .with_context(|| format!("Cannot tell the format of {}; pass --format", output))?,
+            None => AudioEncoding::Wav16,
+        },
+        (None, _) => AudioEncoding::Wav16,
+    };
+
+    let style_paths = args.voice.style_paths()?;
+
+    for caption in &args.subtitles {
+        if caption != "srt" && caption != "vtt" {
+            anyhow::bail!("Unknown subtitle format: {} (expected srt or vtt)", caption);
+        }
+    }
+    let use_ssml = !args.batch && is_ssml(&texts[0]);
+    if !args.subtitles.is_empty() && (args.batch || use_ssml || to_stdout) {
+        anyhow::bail!("--subtitles needs a single plain-text input written to a file");
+    }
+    if args.batch {
+        if texts.iter().any(|text| is_ssml(text)) {
+            anyhow::bail!("SSML input is not supported in batch mode");
+        }
+        if style_paths.len() != texts.len() || langs.len() != texts.len() {
+            anyhow::bail!(
+                "Batch mode needs one voice and one language per text ({} texts, {} voices, {} languages)",
+                texts.len(),
+                style_paths.len(),
+                langs.len()
+            );
+        }
+    }
+
+    let postprocess = PostProcess {
+        trim: args.trim.then(TrimOptions::default),
+        target_lufs: args.target_lufs,
+        peak_ceiling_db: args.peak_ceiling,
+    };
+    let output_postprocess = if args.postprocess_chunks && !args.batch {
+        options.chunk_postprocess = postprocess;
+        PostProcess::default()
+    } else {
+        postprocess
+    };
+
+    let mut tts = args.engine.load()?;
+    let sample_rate = tts.output_sample_rate(&options);
+    let style = load_voice_style(&style_paths, true)?;
+
+    let (wav, durations, chunk_timings) = if args.batch {
+        let (wav, durations) = timer("Generating speech from text", || tts.batch(&texts, langs, &style, &options))?;
+        (wav, durations, Vec::new())
+    } else if use_ssml {
+        let (wav, duration) = timer("Generating speech from SSML", || {
+            synthesize_ssml(&mut tts, &texts[0], &langs[0], &style, &style_paths[0], &options)
+        })?;
+        (wav, vec![duration], Vec::new())
+    } else {
+        let (wav, duration, timings) = timer("Generating speech from text", || {
+            tts.call_with_timing(&texts[0], &langs[0], &style, &options, |_, _, _| true)
+        })?;
+        (wav, vec![duration], timings)
+    };
+
+    for (i, text) in texts.iter().enumerate() {
+        // Batch output holds every item padded to the same length
+        let len = wav.len() / texts.len();
+        let start = i * len;
+        let actual_len = ((sample_rate as f32 * durations[i]) as usize).min(len);
+        let mut output = wav[start..start + actual_len].to_vec();
+        let report = output_postprocess.apply(&mut output, sample_rate);
+
+        if to_stdout {
+            let bytes = encode_audio(&output, sample_rate as u32, format)?;
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&bytes)?;
+            stdout.flush()?;
+            continue;
+        }
+
+        let output_path = match &args.output {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let stem = sanitize_filename(text, 20);
+                let name = if args.batch { format!("{}_{}", stem, i + 1) } else { stem };
+                PathBuf::from(&args.out_dir).join(format!("{}.{}", name, format.extension()))
+            }
+        };
+        if let Some(dir) = output_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        write_audio_file(&output_path, &output, sample_rate as u32, format)?;
+        eprintln!("Saved: {}", output_path.display());
+
+        if !args.subtitles.is_empty() {
+            let trimmed_lead = report.trimmed_start as f32 / sample_rate as f32;
+            let cues = build_cues(text, &shift_timings(&chunk_timings, -trimmed_lead), DEFAULT_MAX_CUE_CHARS);
+            for caption in &args.subtitles {
+                let caption_path = output_path.with_extension(caption);
+                if caption == "srt" {
+                    write_srt(&caption_path, &cues)?;
+                } else {
+                    write_vtt(&caption_path, &cues)?;
+                }
+                eprintln!("Saved: {}", caption_path.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list_voices(args: VoicesArgs) -> Result<()> {
+    let registry = VoiceRegistry::scan(&args.voice_dir)?;
+    if args.json {
+        println!("{}", registry.to_json()?);
+        return Ok(());
+    }
+    let voices: Vec<_> = match &args.lang {
+        Some(lang) => registry.for_language(lang).collect(),
+        None => registry.voices().iter().collect(),
+    };
+    for voice in voices {
+        let default = registry.default_voice().is_some_and(|d| d.id == voice.id);
+        println!(
+            "{:<8} {}{}  {}",
+            voice.id,
+            voice.display_name(),
+            if default { " (default)" } else { "" },
+            voice.path.display()
+        );
+    }
+    Ok(())
+}
+
+fn bench(args: BenchArgs) -> Result<()> {
+    if args.runs == 0 {
+        anyhow::bail!("--runs must be at least 1");
+    }
+    // Each argument or input line is one text, so lengths can be compared
+    let texts = if args.input.is_empty() {
+        DEFAULT_TEXTS.iter().map(|t| t.to_string()).collect()
+    } else {
+        args.input.read(true)?
+    };
+    let lang = &args.synthesis.lang[0];
+    let options = args.synthesis.options();
+    let steps = if args.steps.is_empty() { vec![options.total_step] } else { args.steps.clone() };
+    let threads: Vec<Option<usize>> = if args.thread_counts.is_empty() {
+        vec![args.engine.threads]
+    } else {
+        args.thread_counts.iter().map(|&n| Some(n)).collect()
+    };
+    let style_paths = args.voice.style_paths()?;
+
+    let mut tts = args.engine.load()?;
+    let style = load_voice_style(&style_paths[..1], false)?;
+
+    let config = BenchConfig {
+        texts: &texts,
+        lang,
+        style: &style,
+        options: &options,
+        steps: &steps,
+        threads: &threads,
+        runs: args.runs,
+        warmup: args.warmup,
+    };
+    let runs = run_benchmark(&mut tts, &config)?;
+    let report = BenchReport {
+        label: args.label.clone(),
+        onnx_dir: args.engine.onnx_dir.clone(),
+        sample_rate: tts.output_sample_rate(&options),
+        lang: lang.clone(),
+        timestamp: bench::timestamp(),
+        warmup: args.warmup,
+        summary: summarize(&runs),
+        runs,
+    };
+
+    if let Some(path) = &args.json {
+        write_json(&report, path)?;
+    }
+    if let Some(path) = &args.csv {
+        write_csv(&report, path)?;
+    }
+    // Keep stdout for the report when it goes there
+    if args.json.as_deref() == Some("-") || args.csv.as_deref() == Some("-") {
+        print_summary(&mut io::stderr().lock(), &report.summary)?;
+    } else {
+        print_summary(&mut io::stdout().lock(), &report.summary)?;
+    }
+    Ok(())
+}
+
+fn style_info(args: StyleInfoArgs) -> Result<()> {
+    for input in &args.inputs {
+        let bytes = fs::read(input).with_context(|| format!("Failed to read {}", input))?;
+        let format = if bytes.starts_with(STYLE_BINARY_MAGIC) {
+            match bytes.get(6) {
+                Some(1) => "binary f16",
+                _ => "binary f32",
+            }
+        } else {
+            "json"
+        };
+        let style = load_voice_style(std::slice::from_ref(input), false)?;
+        println!("{}: {} ttl={:?} dp={:?}", input, format, style.ttl.dim(), style.dp.dim());
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    match Cli::parse().command {
+        Command::Synth(args) => synth(args),
+        Command::Voices(args) => list_voices(args),
+        Command::Bench(args) => bench(args),
+        Command::ConvertStyle(args) => {
+            let dtype = if args.f16 { StyleDtype::F16 } else { StyleDtype::F32 };
+            convert_voice_styles(&args.inputs, args.out_dir.as_deref(), dtype)
+        }
+        Command::StyleInfo(args) => style_info(args),
+    }
+}
@@ -0,0 +1,600 @@
+// ============================================================================
+// Audio Encoders - WAV (16/24-bit, 32-bit float), FLAC and Ogg/Opus
+// ============================================================================
+//
+// All encoders take mono f32 samples in [-1, 1]. 16-bit output is TPDF
+// dithered; FLAC is encoded here with fixed predictors and partitioned Rice
+// coding; Ogg/Opus needs the `opus` feature (libopus via audiopus).
+
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavSpec, WavWriter};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::io::Cursor;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioEncoding {
+    /// 16-bit PCM WAV with TPDF dither
+    Wav16,
+    /// 24-bit PCM WAV
+    Wav24,
+    /// 32-bit float WAV
+    WavF32,
+    /// 16-bit FLAC with TPDF dither
+    Flac16,
+    /// 24-bit FLAC
+    Flac24,
+    /// Opus in an Ogg container, at `bitrate` bits per second
+    OggOpus { bitrate: u32 },
+}
+
+pub const DEFAULT_OPUS_BITRATE: u32 = 32_000;
+
+impl AudioEncoding {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav16 | AudioEncoding::Wav24 | AudioEncoding::WavF32 => "wav",
+            AudioEncoding::Flac16 | AudioEncoding::Flac24 => "flac",
+            AudioEncoding::OggOpus { .. } => "opus",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioEncoding::Wav16 | AudioEncoding::Wav24 | AudioEncoding::WavF32 => "audio/wav",
+            AudioEncoding::Flac16 | AudioEncoding::Flac24 => "audio/flac",
+            AudioEncoding::OggOpus { .. } => "audio/ogg; codecs=opus",
+        }
+    }
+}
+
+impl FromStr for AudioEncoding {
+    type Err = anyhow::Error;
+
+    /// `wav`, `wav16`, `wav24`, `wav32f`, `flac`, `flac16`, `flac24`,
+    /// `opus` or `opus:<kbps>`
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim().to_ascii_lowercase();
+        if let Some(kbps) = s.strip_prefix("opus:") {
+            let kbps: u32 = kbps.parse().with_context(|| format!("Invalid Opus bitrate: {}", kbps))?;
+            return Ok(AudioEncoding::OggOpus { bitrate: kbps * 1000 });
+        }
+        match s.as_str() {
+            "wav" | "wav16" => Ok(AudioEncoding::Wav16),
+            "wav24" => Ok(AudioEncoding::Wav24),
+            "wav32f" | "wavf32" | "wav-float" => Ok(AudioEncoding::WavF32),
+            "flac" | "flac16" => Ok(AudioEncoding::Flac16),
+            "flac24" => Ok(AudioEncoding::Flac24),
+            "opus" | "ogg" => Ok(AudioEncoding::OggOpus { bitrate: DEFAULT_OPUS_BITRATE }),
+            _ => anyhow::bail!(
+                "Unknown audio format '{}' (expected wav, wav24, wav32f, flac, flac24 or opus[:kbps])",
+                s
+            ),
+        }
+    }
+}
+
+/// Encode `samples` as a complete file in memory
+pub fn encode_audio(samples: &[f32], sample_rate: u32, encoding: AudioEncoding) -> Result<Vec<u8>> {
+    match encoding {
+        AudioEncoding::Wav16 => encode_wav(samples, sample_rate, 16),
+        AudioEncoding::Wav24 => encode_wav(samples, sample_rate, 24),
+        AudioEncoding::WavF32 => encode_wav(samples, sample_rate, 32),
+        AudioEncoding::Flac16 => encode_flac(samples, sample_rate, 16),
+        AudioEncoding::Flac24 => encode_flac(samples, sample_rate, 24),
+        AudioEncoding::OggOpus { bitrate } => encode_ogg_opus(samples, sample_rate, bitrate),
+    }
+}
+
+pub fn write_audio_file<P: AsRef<Path>>(
+    path: P,
+    samples: &[f32],
+    sample_rate: u32,
+    encoding: AudioEncoding,
+) -> Result<()> {
+    let bytes = encode_audio(samples, sample_rate, encoding)?;
+    std::fs::write(path.as_ref(), bytes)
+        .with_context(|| format!("Failed to write {}", path.as_ref().display()))?;
+    Ok(())
+}
+
+// ============================================================================
+// Quantization
+// ============================================================================
+
+/// Triangular (TPDF) dither noise of +-1 LSB. Keep one per output stream:
+/// consecutive chunks then get different noise instead of repeating the same
+/// sequence, while the same audio still always encodes to the same bytes.
+pub struct Dither {
+    rng: ChaCha8Rng,
+}
+
+impl Dither {
+    pub fn new() -> Self {
+        Dither { rng: ChaCha8Rng::seed_from_u64(0) }
+    }
+
+    fn noise(&mut self) -> f32 {
+        self.rng.gen::<f32>() - self.rng.gen::<f32>()
+    }
+
+    /// 16-bit little-endian PCM bytes, continuing this stream's noise
+    pub fn pcm16(&mut self, samples: &[f32]) -> Vec<u8> {
+        quantize(samples, 16, Some(self))
+            .into_iter()
+            .flat_map(|v| (v as i16).to_le_bytes())
+            .collect()
+    }
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Convert to `bits`-bit integers with rounding. With `dither`, its noise
+/// decorrelates the quantization error from the signal.
+pub fn quantize(samples: &[f32], bits: u32, mut dither: Option<&mut Dither>) -> Vec<i32> {
+    let scale = ((1i64 << (bits - 1)) - 1) as f32;
+    let (min, max) = (-scale - 1.0, scale);
+
+    samples
+        .iter()
+        .map(|&s| {
+            let mut v = s.clamp(-1.0, 1.0) * scale;
+            if let Some(dither) = dither.as_deref_mut() {
+                v += dither.noise();
+            }
+            v.round().clamp(min, max) as i32
+        })
+        .collect()
+}
+
+/// 16-bit little-endian PCM bytes with TPDF dither, for audio sent in one piece
+pub fn to_pcm16_dithered(samples: &[f32]) -> Vec<u8> {
+    Dither::new().pcm16(samples)
+}
+
+// ============================================================================
+// WAV
+// ============================================================================
+
+fn encode_wav(samples: &[f32], sample_rate: u32, bits: u16) -> Result<Vec<u8>> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: bits,
+        sample_format: if bits == 32 { SampleFormat::Float } else { SampleFormat::Int },
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = WavWriter::new(&mut cursor, spec)?;
+        match bits {
+            32 => {
+                for &s in samples {
+                    writer.write_sample(s)?;
+                }
+            }
+            16 => {
+                for v in quantize(samples, 16, Some(&mut Dither::new())) {
+                    writer.write_sample(v as i16)?;
+                }
+            }
+            _ => {
+                for v in quantize(samples, bits as u32, None) {
+                    writer.write_sample(v)?;
+                }
+            }
+        }
+        writer.finalize()?;
+    }
+    Ok(cursor.into_inner())
+}
+
+// ============================================================================
+// FLAC
+// ============================================================================
+
+const FLAC_BLOCK_SIZE: usize = 4096;
+const FLAC_MAX_FIXED_ORDER: usize = 4;
+const FLAC_MAX_PARTITION_ORDER: u32 = 6;
+
+/// MSB-first bit writer
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter { bytes: Vec::new(), acc: 0, bits: 0 }
+    }
+
+    fn write(&mut self, value: u64, bits: u32) {
+        for i in (0..bits).rev() {
+            self.acc = (self.acc << 1) | ((value >> i) & 1);
+            self.bits += 1;
+            if self.bits == 8 {
+                self.bytes.push(self.acc as u8);
+                self.acc = 0;
+                self.bits = 0;
+            }
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write((value as u64) & ((1u64 << bits) - 1), bits);
+    }
+
+    fn write_unary(&mut self, zeros: u64) {
+        for _ in 0..zeros {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+    for &byte in data {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0u16;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x8005 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// FLAC's UTF-8-like variable length integer (frame numbers)
+fn write_utf8_number(w: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        w.write(n, 8);
+        return;
+    }
+    let continuation_bytes = match n {
+        0..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        _ => 5,
+    };
+    let lead_ones = (0xFF00u16 >> (continuation_bytes + 1)) as u8;
+    w.write((lead_ones as u64) | (n >> (6 * continuation_bytes)), 8);
+    for i in (0..continuation_bytes).rev() {
+        w.write(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn fixed_residuals(block: &[i32], order: usize) -> Vec<i64> {
+    let x = |i: usize| block[i] as i64;
+    (order..block.len())
+        .map(|i| match order {
+            0 => x(i),
+            1 => x(i) - x(i - 1),
+            2 => x(i) - 2 * x(i - 1) + x(i - 2),
+            3 => x(i) - 3 * x(i - 1) + 3 * x(i - 2) - x(i - 3),
+            _ => x(i) - 4 * x(i - 1) + 6 * x(i - 2) - 4 * x(i - 3) + x(i - 4),
+        })
+        .collect()
+}
+
+fn zigzag(r: i64) -> u64 {
+    ((r << 1) ^ (r >> 63)) as u64
+}
+
+fn rice_bits(values: &[u64], k: u32) -> u64 {
+    values.iter().map(|&u| (u >> k) + 1 + k as u64).sum()
+}
+
+/// Cheapest Rice parameter for `values` and its cost in bits
+fn best_rice_param(values: &[u64]) -> (u32, u64) {
+    if values.is_empty() {
+        return (0, 0);
+    }
+    let mean = values.iter().sum::<u64>() / values.len() as u64;
+    let guess = if mean == 0 { 0 } else { 63 - mean.leading_zeros() };
+    (guess.saturating_sub(1)..=(guess + 1).min(30))
+        .map(|k| (k, rice_bits(values, k)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap()
+}
+
+/// Rice-coded residual of one FIXED predictor order
+struct RiceCoding {
+    order: usize,
+    residual: Vec<u64>,
+    partition_order: u32,
+    params: Vec<u32>,
+    bits: u64,
+}
+
+/// Cheapest partitioning of the zigzagged `residual` of a block
+fn best_partitioning(residual: Vec<u64>, block_len: usize, order: usize) -> RiceCoding {
+    let mut best = RiceCoding { order, residual: Vec::new(), partition_order: 0, params: vec![0], bits: u64::MAX };
+    for partition_order in 0..=FLAC_MAX_PARTITION_ORDER {
+        let partitions = 1usize << partition_order;
+        if !block_len.is_multiple_of(partitions) || block_len / partitions <= order {
+            break;
+        }
+        let part_len = block_len / partitions;
+        let mut params = Vec::with_capacity(partitions);
+        let mut bits = 0;
+        let mut offset = 0;
+        for p in 0..partitions {
+            let len = if p == 0 { part_len - order } else { part_len };
+            let (k, cost) = best_rice_param(&residual[offset..offset + len]);
+            params.push(k);
+            bits += cost + 4;
+            offset += len;
+        }
+        if bits < best.bits {
+            best.partition_order = partition_order;
+            best.params = params;
+            best.bits = bits;
+        }
+    }
+    best.residual = residual;
+    best
+}
+
+fn write_subframe(w: &mut BitWriter, block: &[i32], bps: u32) {
+    if block.iter().all(|&v| v == block[0]) {
+        w.write(0b0000_0000, 8);
+        w.write_signed(block[0] as i64, bps);
+        return;
+    }
+
+    let verbatim_bits = block.len() as u64 * bps as u64;
+    let best = (0..=FLAC_MAX_FIXED_ORDER.min(block.len() - 1))
+        .map(|order| {
+            let residual = fixed_residuals(block, order).into_iter().map(zigzag).collect();
+            let mut coding = best_partitioning(residual, block.len(), order);
+            coding.bits = coding.bits.saturating_add(order as u64 * bps as u64);
+            coding
+        })
+        .min_by_key(|coding| coding.bits)
+        .filter(|coding| coding.bits < verbatim_bits);
+
+    match best {
+        Some(coding) => {
+            let order = coding.order;
+            w.write(0b0001_0000 | ((order as u64) << 1), 8);
+            for &v in &block[..order] {
+                w.write_signed(v as i64, bps);
+            }
+            // RICE2 (5-bit parameters) only when a parameter needs it
+            let rice2 = coding.params.iter().any(|&k| k >= 15);
+            let param_bits = if rice2 { 5 } else { 4 };
+            w.write(rice2 as u64, 2);
+            w.write(coding.partition_order as u64, 4);
+            let part_len = block.len() >> coding.partition_order;
+            let mut offset = 0;
+            for (p, &k) in coding.params.iter().enumerate() {
+                let len = if p == 0 { part_len - order } else { part_len };
+                w.write(k as u64, param_bits);
+                for &u in &coding.residual[offset..offset + len] {
+                    w.write_unary(u >> k);
+                    w.write(u & ((1u64 << k) - 1), k);
+                }
+                offset += len;
+            }
+        }
+        None => {
+            w.write(0b0000_0010, 8);
+            for &v in block {
+                w.write_signed(v as i64, bps);
+            }
+        }
+    }
+}
+
+fn encode_flac(samples: &[f32], sample_rate: u32, bps: u32) -> Result<Vec<u8>> {
+    if sample_rate == 0 || sample_rate >= 1 << 20 {
+        anyhow::bail!("Sample rate {} cannot be stored in FLAC", sample_rate);
+    }
+    let mut dither = Dither::new();
+    let pcm = quantize(samples, bps, (bps == 16).then_some(&mut dither));
+
+    let mut md5 = md5::Context::new();
+    for &v in &pcm {
+        md5.consume(&v.to_le_bytes()[..(bps / 8) as usize]);
+    }
+
+    let mut frames = Vec::new();
+    let (mut min_frame, mut max_frame) = (u32::MAX, 0u32);
+    for (frame_number, block) in pcm.chunks(FLAC_BLOCK_SIZE).enumerate() {
+        let mut w = BitWriter::new();
+        // Sync code, fixed block size, 16-bit block size at end of header,
+        // sample rate from STREAMINFO, mono
+        w.write(0b1111_1111_1111_1000, 16);
+        w.write(0b0111, 4);
+        w.write(0b0000, 4);
+        w.write(0b0000, 4);
+        w.write(if bps == 16 { 0b100 } else { 0b110 }, 3);
+        w.write(0, 1);
+        write_utf8_number(&mut w, frame_number as u64);
+        w.write((block.len() - 1) as u64, 16);
+        let crc = crc8(&w.bytes);
+        w.write(crc as u64, 8);
+
+        write_subframe(&mut w, block, bps);
+        w.align();
+        let crc = crc16(&w.bytes);
+        w.write(crc as u64, 16);
+
+        let len = w.bytes.len() as u32;
+        min_frame = min_frame.min(len);
+        max_frame = max_frame.max(len);
+        frames.extend_from_slice(&w.bytes);
+    }
+
+    let block_size = FLAC_BLOCK_SIZE.min(pcm.len().max(16)) as u64;
+    let mut header = BitWriter::new();
+    header.bytes.extend_from_slice(b"fLaC");
+    // Last metadata block, STREAMINFO, 34 bytes
+    header.write(1, 1);
+    header.write(0, 7);
+    header.write(34, 24);
+    header.write(block_size, 16);
+    header.write(block_size, 16);
+    header.write(if frames.is_empty() { 0 } else { min_frame as u64 }, 24);
+    header.write(max_frame as u64, 24);
+    header.write(sample_rate as u64, 20);
+    header.write(0, 3);
+    header.write((bps - 1) as u64, 5);
+    header.write(pcm.len() as u64, 36);
+    for byte in md5.compute().0 {
+        header.write(byte as u64, 8);
+    }
+
+    let mut out = header.bytes;
+    out.extend_from_slice(&frames);
+    Ok(out)
+}
+
+// ============================================================================
+// Ogg/Opus
+// ============================================================================
+
+#[cfg(feature = "opus")]
+fn encode_ogg_opus(samples: &[f32], sample_rate: u32, bitrate: u32) -> Result<Vec<u8>> {
+    use audiopus::coder::Encoder;
+    use audiopus::{Application, Bitrate, Channels, SampleRate};
+    use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+    const OPUS_RATE: u32 = 48_000;
+    const FRAME: usize = 960; // 20 ms at 48 kHz
+    const SERIAL: u32 = 0x5375_7065;
+
+    let pcm = crate::resample::resample(samples, sample_rate, OPUS_RATE)?;
+
+    let mut encoder = Encoder::new(SampleRate::Hz48000, Channels::Mono, Application::Voip)
+        .map_err(|e| anyhow::anyhow!("Failed to create Opus encoder: {}", e))?;
+    encoder
+        .set_bitrate(Bitrate::BitsPerSecond(bitrate as i32))
+        .map_err(|e| anyhow::anyhow!("Invalid Opus bitrate {}: {}", bitrate, e))?;
+    let pre_skip = encoder.lookahead().map_err(|e| anyhow::anyhow!("{}", e))? as u64;
+
+    let mut writer = PacketWriter::new(Vec::new());
+
+    let mut head = Vec::with_capacity(19);
+    head.extend_from_slice(b"OpusHead");
+    head.push(1);
+    head.push(1);
+    head.extend_from_slice(&(pre_skip as u16).to_le_bytes());
+    head.extend_from_slice(&sample_rate.to_le_bytes());
+    head.extend_from_slice(&0i16.to_le_bytes());
+    head.push(0);
+    writer.write_packet(head.into_boxed_slice(), SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    let vendor = concat!("supertonic-tts ", env!("CARGO_PKG_VERSION"));
+    let mut tags = Vec::new();
+    tags.extend_from_slice(b"OpusTags");
+    tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+    tags.extend_from_slice(vendor.as_bytes());
+    tags.extend_from_slice(&0u32.to_le_bytes());
+    writer.write_packet(tags.into_boxed_slice(), SERIAL, PacketWriteEndInfo::EndPage, 0)?;
+
+    // Pad so the encoder's look-ahead is flushed and the last frame is full
+    let total = pcm.len() as u64;
+    let mut input = pcm;
+    let padded_len = (input.len() + pre_skip as usize).div_ceil(FRAME) * FRAME;
+    input.resize(padded_len.max(FRAME), 0.0);
+
+    let frame_count = input.len() / FRAME;
+    let mut packet = vec![0u8; 4000];
+    for (i, frame) in input.chunks_exact(FRAME).enumerate() {
+        let len = encoder
+            .encode_float(frame, &mut packet)
+            .map_err(|e| anyhow::anyhow!("Opus encoding failed: {}", e))?;
+        let last = i + 1 == frame_count;
+        let granule = if last { pre_skip + total } else { ((i + 1) * FRAME) as u64 };
+        let end = if last { PacketWriteEndInfo::EndStream } else { PacketWriteEndInfo::NormalPacket };
+        writer.write_packet(packet[..len].to_vec().into_boxed_slice(), SERIAL, end, granule)?;
+    }
+
+    Ok(writer.into_inner())
+}
+
+#[cfg(not(feature = "opus"))]
+fn encode_ogg_opus(_samples: &[f32], _sample_rate: u32, _bitrate: u32) -> Result<Vec<u8>> {
+    anyhow::bail!("Ogg/Opus output needs a build with the `opus` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, sample_rate: u32) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn decode_flac(bytes: &[u8]) -> (claxon::metadata::StreamInfo, Vec<i32>) {
+        let mut reader = claxon::FlacReader::new(Cursor::new(bytes)).unwrap();
+        let info = reader.streaminfo();
+        let samples = reader.samples().collect::<Result<Vec<_>, _>>().unwrap();
+        (info, samples)
+    }
+
+    #[test]
+    fn flac_round_trip_is_lossless() {
+        // Several full blocks plus a partial one
+        let samples = sine(FLAC_BLOCK_SIZE * 2 + 1000, 24_000);
+        for (encoding, bits) in [(AudioEncoding::Flac16, 16), (AudioEncoding::Flac24, 24)] {
+            let bytes = encode_audio(&samples, 24_000, encoding).unwrap();
+            let (info, decoded) = decode_flac(&bytes);
+            assert_eq!(info.sample_rate, 24_000);
+            assert_eq!(info.bits_per_sample, bits);
+            assert_eq!(info.channels, 1);
+            assert_eq!(info.samples, Some(samples.len() as u64));
+
+            let mut dither = Dither::new();
+            let expected = quantize(&samples, bits, (bits == 16).then_some(&mut dither));
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn flac_round_trip_of_silence_and_full_scale() {
+        let mut samples = vec![0.0; 5000];
+        samples.extend((0..5000).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }));
+        let bytes = encode_audio(&samples, 44_100, AudioEncoding::Flac24).unwrap();
+        let (_, decoded) = decode_flac(&bytes);
+        assert_eq!(decoded, quantize(&samples, 24, None));
+    }
+
+    #[test]
+    fn dither_continues_across_chunks() {
+        let silence = vec![0.0; 256];
+        let mut dither = Dither::new();
+        let first = dither.pcm16(&silence);
+        let second = dither.pcm16(&silence);
+        assert_ne!(first, second);
+        // A fresh stream starts over
+        assert_eq!(Dither::new().pcm16(&silence), first);
+    }
+}
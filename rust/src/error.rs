@@ -0,0 +1,138 @@
+// ============================================================================
+// JNI Error Reporting - typed errors thrown as Java exceptions
+// ============================================================================
+
+use jni::JNIEnv;
+use jni::objects::{JString, JValue};
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Kotlin class thrown for every engine failure (see SupertonicException.kt)
+const EXCEPTION_CLASS: &str = "com/brahmadeo/supertonic/tts/SupertonicException";
+
+#[derive(Debug)]
+pub enum EngineError {
+    /// The engine pointer is null (not initialized or already released)
+    NotInitialized,
+    /// A malformed argument, e.g. a bad `path1;path2;alpha` mix string
+    InvalidArgument(String),
+    /// ONNX Runtime or the models failed to load
+    ModelLoad(anyhow::Error),
+    /// A voice style file is missing or invalid
+    VoiceStyle(anyhow::Error),
+    /// Inference failed
+    Synthesis(anyhow::Error),
+    /// `isCancelled()` returned true during synthesis
+    Cancelled,
+    /// A JNI call failed (a Java exception may already be pending)
+    Jni(jni::errors::Error),
+    /// Rust code panicked; the panic was caught at the FFI boundary
+    Panic(String),
+}
+
+impl EngineError {
+    /// Stable code exposed as `SupertonicException.code`
+    pub fn code(&self) -> i32 {
+        match self {
+            EngineError::NotInitialized => 1,
+            EngineError::InvalidArgument(_) => 2,
+            EngineError::ModelLoad(_) => 3,
+            EngineError::VoiceStyle(_) => 4,
+            EngineError::Synthesis(_) => 5,
+            EngineError::Cancelled => 6,
+            EngineError::Jni(_) => 7,
+            EngineError::Panic(_) => 8,
+        }
+    }
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::NotInitialized => write!(f, "Engine is not initialized"),
+            EngineError::InvalidArgument(msg) => write!(f, "Invalid argument: {}", msg),
+            EngineError::ModelLoad(e) => write!(f, "Failed to load models: {:#}", e),
+            EngineError::VoiceStyle(e) => write!(f, "Failed to load voice style: {:#}", e),
+            EngineError::Synthesis(e) => write!(f, "Synthesis failed: {:#}", e),
+            EngineError::Cancelled => write!(f, "Synthesis cancelled"),
+            EngineError::Jni(e) => write!(f, "JNI call failed: {}", e),
+            EngineError::Panic(msg) => write!(f, "Native panic: {}", msg),
+        }
+    }
+}
+
+impl From<jni::errors::Error> for EngineError {
+    fn from(e: jni::errors::Error) -> Self {
+        EngineError::Jni(e)
+    }
+}
+
+pub type EngineResult<T> = Result<T, EngineError>;
+
+/// Read a Java string argument
+pub fn get_string(env: &mut JNIEnv, value: &JString, name: &str) -> EngineResult<String> {
+    if value.is_null() {
+        return Err(EngineError::InvalidArgument(format!("{} is null", name)));
+    }
+    Ok(env.get_string(value)?.into())
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Throw `error` as a `SupertonicException`, falling back to
+/// `RuntimeException` if the class cannot be found. An exception that is
+/// already pending (e.g. thrown by a Java callback) is left in place.
+pub fn throw(env: &mut JNIEnv, error: &EngineError) {
+    if env.exception_check().unwrap_or(false) {
+        return;
+    }
+    let message = error.to_string();
+
+    let thrown = (|| -> jni::errors::Result<()> {
+        let jmessage = env.new_string(&message)?;
+        let exception = env.new_object(
+            EXCEPTION_CLASS,
+            "(ILjava/lang/String;)V",
+            &[JValue::Int(error.code()), JValue::Object(&jmessage)],
+        )?;
+        env.throw(jni::objects::JThrowable::from(exception))
+    })();
+
+    if thrown.is_err() {
+        let _ = env.exception_clear();
+        let _ = env.throw_new("java/lang/RuntimeException", &message);
+    }
+}
+
+/// Run a JNI entry point: errors are logged and thrown as Java exceptions,
+/// panics are caught so they never unwind into the JVM, and `default` is
+/// returned to Java in both cases.
+pub fn guard<R, F>(env: &mut JNIEnv, default: R, f: F) -> R
+where
+    F: FnOnce(&mut JNIEnv) -> EngineResult<R>,
+{
+    let result = panic::catch_unwind(AssertUnwindSafe(|| f(&mut *env)))
+        .unwrap_or_else(|payload| Err(EngineError::Panic(panic_message(payload.as_ref()))));
+
+    match result {
+        Ok(value) => value,
+        Err(error) => {
+            if matches!(error, EngineError::Cancelled) {
+                log::info!("{}", error);
+            } else {
+                log::error!("{}", error);
+            }
+            throw(env, &error);
+            default
+        }
+    }
+}
@@ -2,18 +2,30 @@
 // TTS Helper Module - All utility functions and structures
 // ============================================================================ 
 
-use ndarray::{Array, Array3};
+use ndarray::{Array, Array3, Axis};
 use serde::{Deserialize, Serialize};
-use serde_json;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
-use anyhow::{Result, Context, bail};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use anyhow::{Result, Context};
 use unicode_normalization::UnicodeNormalization;
-use hound::{WavWriter, WavSpec, SampleFormat};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
 use rand_distr::{Distribution, Normal};
 use regex::Regex;
 
+use crate::encoder::{write_audio_file, AudioEncoding};
+use crate::lexicon::Lexicon;
+use crate::normalizer::normalize_text;
+use crate::postprocess::PostProcess;
+use crate::resample::{resample, Resampler};
+use crate::ssml::{parse_ssml, SsmlBoundary};
+use crate::timing::{estimate_word_spans, TextAligner, WordTiming};
+
 // Available languages for multilingual TTS
 pub const AVAILABLE_LANGS: &[&str] = &["en", "ko", "es", "pt", "fr"];
 
@@ -86,12 +98,8 @@ impl UnicodeProcessor {
         Ok(UnicodeProcessor { indexer })
     }
 
-    pub fn call(&self, text_list: &[String], lang_list: &[String]) -> Result<(Vec<Vec<i64>>, Array3<f32>)> {
-        let mut processed_texts: Vec<String> = Vec::new();
-        for (text, lang) in text_list.iter().zip(lang_list.iter()) {
-            processed_texts.push(preprocess_text(text, lang)?);
-        }
-
+    /// Token ids and mask for texts already run through `preprocess_text`
+    pub fn encode(&self, processed_texts: &[String]) -> (Vec<Vec<i64>>, Array3<f32>) {
         let text_ids_lengths: Vec<usize> = processed_texts
             .iter()
             .map(|t| t.chars().count())
@@ -100,7 +108,7 @@ impl UnicodeProcessor {
         let max_len = *text_ids_lengths.iter().max().unwrap_or(&0);
 
         let mut text_ids = Vec::new();
-        for text in &processed_texts {
+        for text in processed_texts {
             let mut row = vec![0i64; max_len];
             let unicode_vals = text_to_unicode_values(text);
             for (j, &val) in unicode_vals.iter().enumerate() {
@@ -116,11 +124,15 @@ impl UnicodeProcessor {
 
         let text_mask = get_text_mask(&text_ids_lengths);
 
-        Ok((text_ids, text_mask))
+        (text_ids, text_mask)
     }
 }
 
 pub fn preprocess_text(text: &str, lang: &str) -> Result<String> {
+    // Spell out numbers, currencies and units before NFKD folds symbols such
+    // as the ordinal indicators "º"/"ª" into plain letters
+    let text = normalize_text(text, lang);
+
     // Revert to NFKD normalization as required for Korean Jamo decomposition
     let mut text: String = text.nfkd().collect();
 
@@ -239,14 +251,22 @@ pub fn get_text_mask(text_ids_lengths: &[usize]) -> Array3<f32> {
     length_to_mask(text_ids_lengths, Some(max_len))
 }
 
-/// Sample noisy latent from normal distribution and apply mask
+/// Sample noisy latent from normal distribution and apply mask.
+///
+/// With a `seed`, row `b` of the batch is drawn from its own RNG seeded with
+/// `seed + b`, so a text always gets the same noise regardless of which batch
+/// it is part of. Noise is drawn frame by frame, so padding a row to a longer
+/// batch only appends to it.
+#[allow(clippy::too_many_arguments)]
 pub fn sample_noisy_latent(
     duration: &[f32],
     sample_rate: i32,
     base_chunk_size: i32,
     chunk_compress: i32,
     latent_dim: i32,
-) -> (Array3<f32>, Array3<f32>) {
+    temperature: f32,
+    seed: Option<u64>,
+) -> Result<(Array3<f32>, Array3<f32>)> {
     let bsz = duration.len();
     let max_dur = duration.iter().fold(0.0f32, |a, &b| a.max(b));
 
@@ -257,18 +277,21 @@ pub fn sample_noisy_latent(
         .collect();
 
     let chunk_size = (base_chunk_size * chunk_compress) as usize;
-    let latent_len = (wav_len_max + chunk_size - 1) / chunk_size;
+    let latent_len = wav_len_max.div_ceil(chunk_size);
     let latent_dim_val = (latent_dim * chunk_compress) as usize;
 
     let mut noisy_latent = Array3::<f32>::zeros((bsz, latent_dim_val, latent_len));
 
-    // Reduced temperature (0.667) improves stability and reduces word skipping/hallucinations
-    let normal = Normal::new(0.0, 0.667).unwrap();
-    let mut rng = rand::thread_rng();
+    let normal = Normal::new(0.0, temperature)
+        .map_err(|e| anyhow::anyhow!("Invalid noise temperature {}: {}", temperature, e))?;
 
     for b in 0..bsz {
-        for d in 0..latent_dim_val {
-            for t in 0..latent_len {
+        let mut rng = match seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed.wrapping_add(b as u64)),
+            None => ChaCha8Rng::from_rng(rand::thread_rng())?,
+        };
+        for t in 0..latent_len {
+            for d in 0..latent_dim_val {
                 noisy_latent[[b, d, t]] = normal.sample(&mut rng);
             }
         }
@@ -276,7 +299,7 @@ pub fn sample_noisy_latent(
 
     let latent_lengths: Vec<usize> = wav_lengths
         .iter()
-        .map(|&len| (len + chunk_size - 1) / chunk_size)
+        .map(|&len| len.div_ceil(chunk_size))
         .collect();
 
     let latent_mask = length_to_mask(&latent_lengths, Some(latent_len));
@@ -290,36 +313,20 @@ pub fn sample_noisy_latent(
         }
     }
 
-    (noisy_latent, latent_mask)
+    Ok((noisy_latent, latent_mask))
 }
 
 // ============================================================================
 // WAV File I/O
 // ============================================================================
 
-#[allow(dead_code)]
+/// 16-bit WAV with TPDF dither; see `encoder` for other formats
 pub fn write_wav_file<P: AsRef<Path>>(
     filename: P,
     audio_data: &[f32],
     sample_rate: i32,
 ) -> Result<()> {
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate: sample_rate as u32,
-        bits_per_sample: 16,
-        sample_format: SampleFormat::Int,
-    };
-
-    let mut writer = WavWriter::create(filename, spec)?;
-
-    for &sample in audio_data {
-        let clamped = sample.max(-1.0).min(1.0);
-        let val = (clamped * 32767.0) as i16;
-        writer.write_sample(val)?;
-    }
-
-    writer.finalize()?;
-    Ok(())
+    write_audio_file(filename, audio_data, sample_rate as u32, AudioEncoding::Wav16)
 }
 
 // ============================================================================ 
@@ -334,18 +341,55 @@ const ABBREVIATIONS: &[&str] = &[
     "Co.", "Corp.", "etc.", "vs.", "i.e.", "e.g.", "Ph.D.",
 ];
 
+/// Maximum chunk length used when synthesizing `lang`
+fn chunk_max_len(lang: &str) -> usize {
+    if lang == "ko" { 120 } else { 300 }
+}
+
+/// What ended a chunk, i.e. the kind of break between it and the next one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkBoundary {
+    /// End of a paragraph (or of the whole text)
+    Paragraph,
+    /// End of a sentence inside a paragraph
+    Sentence,
+    /// A comma inside a sentence that was too long for one chunk
+    Clause,
+    /// A forced split between words of an overlong clause
+    Word,
+}
+
+/// A chunk of text together with the break that follows it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextChunk {
+    pub text: String,
+    pub boundary: ChunkBoundary,
+}
+
 pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
+    split_text_chunks(text, max_len)
+        .into_iter()
+        .map(|chunk| chunk.text)
+        .collect()
+}
+
+/// Like `chunk_text`, also reporting the boundary type after each chunk
+pub fn split_text_chunks(text: &str, max_len: Option<usize>) -> Vec<TextChunk> {
     let max_len = max_len.unwrap_or(MAX_CHUNK_LENGTH);
     let text = text.trim();
     
     if text.is_empty() {
-        return vec![String::new()];
+        return vec![TextChunk { text: String::new(), boundary: ChunkBoundary::Paragraph }];
     }
 
     // Split by paragraphs
     let para_re = Regex::new(r"\n\s*\n").unwrap();
     let paragraphs: Vec<&str> = para_re.split(text).collect();
     let mut chunks = Vec::new();
+    let mut push = |chunk: &str, boundary: ChunkBoundary| {
+        chunks.push(TextChunk { text: chunk.trim().to_string(), boundary });
+    };
 
     for para in paragraphs {
         let para = para.trim();
@@ -354,7 +398,7 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
         }
 
         if para.len() <= max_len {
-            chunks.push(para.to_string());
+            push(para, ChunkBoundary::Paragraph);
             continue;
         }
 
@@ -373,14 +417,15 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
             if sentence_len > max_len {
                 // If sentence is longer than max_len, split by comma or space
                 if !current.is_empty() {
-                    chunks.push(current.trim().to_string());
+                    push(&current, ChunkBoundary::Sentence);
                     current.clear();
                     current_len = 0;
                 }
 
                 // Try splitting by comma
                 let parts: Vec<&str> = sentence.split(',').collect();
-                for part in parts {
+                let num_parts = parts.len();
+                for (part_index, part) in parts.into_iter().enumerate() {
                     let part = part.trim();
                     if part.is_empty() {
                         continue;
@@ -388,6 +433,13 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
 
                     let part_len = part.len();
                     if part_len > max_len {
+                        // Keep earlier parts ahead of this one
+                        if !current.is_empty() {
+                            push(&current, ChunkBoundary::Clause);
+                            current.clear();
+                            current_len = 0;
+                        }
+
                         // Split by space as last resort
                         let words: Vec<&str> = part.split_whitespace().collect();
                         let mut word_chunk = String::new();
@@ -396,7 +448,7 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
                         for word in words {
                             let word_len = word.len();
                             if word_chunk_len + word_len + 1 > max_len && !word_chunk.is_empty() {
-                                chunks.push(word_chunk.trim().to_string());
+                                push(&word_chunk, ChunkBoundary::Word);
                                 word_chunk.clear();
                                 word_chunk_len = 0;
                             }
@@ -410,11 +462,16 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
                         }
 
                         if !word_chunk.is_empty() {
-                            chunks.push(word_chunk.trim().to_string());
+                            let boundary = if part_index + 1 == num_parts {
+                                ChunkBoundary::Sentence
+                            } else {
+                                ChunkBoundary::Clause
+                            };
+                            push(&word_chunk, boundary);
                         }
                     } else {
                         if current_len + part_len + 1 > max_len && !current.is_empty() {
-                            chunks.push(current.trim().to_string());
+                            push(&current, ChunkBoundary::Clause);
                             current.clear();
                             current_len = 0;
                         }
@@ -431,7 +488,7 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
             }
 
             if current_len + sentence_len + 1 > max_len && !current.is_empty() {
-                chunks.push(current.trim().to_string());
+                push(&current, ChunkBoundary::Sentence);
                 current.clear();
                 current_len = 0;
             }
@@ -445,12 +502,12 @@ pub fn chunk_text(text: &str, max_len: Option<usize>) -> Vec<String> {
         }
 
         if !current.is_empty() {
-            chunks.push(current.trim().to_string());
+            push(&current, ChunkBoundary::Paragraph);
         }
     }
 
     if chunks.is_empty() {
-        vec![String::new()]
+        vec![TextChunk { text: String::new(), boundary: ChunkBoundary::Paragraph }]
     } else {
         chunks
     }
@@ -507,20 +564,18 @@ fn split_sentences(text: &str) -> Vec<String> {
 // Utility Functions
 // ============================================================================
 
-#[allow(dead_code)]
 pub fn timer<F, T>(name: &str, f: F) -> Result<T>
 where
     F: FnOnce() -> Result<T>,
 {
     let start = std::time::Instant::now();
-    println!("{}...", name);
+    eprintln!("{}...", name);
     let result = f()?;
     let elapsed = start.elapsed().as_secs_f64();
-    println!("  -> {} completed in {:.2} sec", name, elapsed);
+    eprintln!("  -> {} completed in {:.2} sec", name, elapsed);
     Ok(result)
 }
 
-#[allow(dead_code)]
 pub fn sanitize_filename(text: &str, max_len: usize) -> String {
     // Take first max_len characters (Unicode code points, not bytes)
     text.chars()
@@ -541,15 +596,207 @@ pub fn sanitize_filename(text: &str, max_len: usize) -> String {
 // ============================================================================ 
 
 use ort::{
-    session::Session,
+    execution_providers::CPUExecutionProvider,
+    session::{builder::GraphOptimizationLevel, Session},
     value::Value,
 };
 
+/// How consecutive chunks are joined: a pause chosen by the boundary type
+/// between them, with short fades around pauses and a crossfade where there
+/// is no pause (seconds throughout)
+#[derive(Debug, Clone)]
+pub struct ChunkJoin {
+    pub paragraph_pause: f32,
+    pub sentence_pause: f32,
+    pub clause_pause: f32,
+    pub word_pause: f32,
+    /// Fade applied to speech next to a pause, so chunks don't start or stop with a click
+    pub fade: f32,
+    /// Overlap between chunks joined without a pause
+    pub crossfade: f32,
+}
+
+impl ChunkJoin {
+    /// Pauses scaled from a single sentence pause (paragraphs get twice as
+    /// long, clauses half, forced word splits none)
+    pub fn with_sentence_pause(sentence_pause: f32) -> Self {
+        ChunkJoin {
+            paragraph_pause: sentence_pause * 2.0,
+            sentence_pause,
+            clause_pause: sentence_pause * 0.5,
+            word_pause: 0.0,
+            ..Default::default()
+        }
+    }
+
+    /// The same pause after every chunk and no fades, as before joins
+    /// depended on the boundary
+    pub fn uniform(pause: f32) -> Self {
+        ChunkJoin {
+            paragraph_pause: pause,
+            sentence_pause: pause,
+            clause_pause: pause,
+            word_pause: pause,
+            fade: 0.0,
+            crossfade: 0.0,
+        }
+    }
+
+    pub fn pause_after(&self, boundary: ChunkBoundary) -> f32 {
+        let pause = match boundary {
+            ChunkBoundary::Paragraph => self.paragraph_pause,
+            ChunkBoundary::Sentence => self.sentence_pause,
+            ChunkBoundary::Clause => self.clause_pause,
+            ChunkBoundary::Word => self.word_pause,
+        };
+        pause.max(0.0)
+    }
+}
+
+impl Default for ChunkJoin {
+    fn default() -> Self {
+        ChunkJoin {
+            paragraph_pause: 0.6,
+            sentence_pause: 0.3,
+            clause_pause: 0.15,
+            word_pause: 0.0,
+            fade: 0.005,
+            crossfade: 0.02,
+        }
+    }
+}
+
+/// Per-request synthesis parameters
+#[derive(Debug, Clone)]
+pub struct SynthesisOptions {
+    /// Number of denoising steps
+    pub total_step: usize,
+    /// Speech speed factor (higher = faster)
+    pub speed: f32,
+    /// Pauses, fades and crossfades between chunks
+    pub join: ChunkJoin,
+    /// Trimming, loudness normalization and limiting applied to every chunk
+    pub chunk_postprocess: PostProcess,
+    /// Seed for the initial noise; `None` draws a fresh one every time
+    pub seed: Option<u64>,
+    /// Standard deviation of the initial noise
+    pub temperature: f32,
+    /// Rate of the returned audio (Hz); `None` keeps the model's rate
+    pub output_sample_rate: Option<u32>,
+    /// Chunks run through the models together in one padded batch. The first
+    /// chunk always runs alone so streaming starts as early as possible.
+    pub chunk_batch_size: usize,
+}
+
+impl Default for SynthesisOptions {
+    fn default() -> Self {
+        SynthesisOptions {
+            total_step: 5,
+            speed: 1.05,
+            join: ChunkJoin::default(),
+            chunk_postprocess: PostProcess::default(),
+            seed: None,
+            // Reduced temperature (0.667) improves stability and reduces word skipping/hallucinations
+            temperature: 0.667,
+            output_sample_rate: None,
+            chunk_batch_size: 1,
+        }
+    }
+}
+
+/// Linear fade-in over the first `len` samples
+fn fade_in(samples: &mut [f32], len: usize) {
+    let len = len.min(samples.len());
+    for (i, sample) in samples[..len].iter_mut().enumerate() {
+        *sample *= i as f32 / len as f32;
+    }
+}
+
+/// Linear fade-out over the last `len` samples
+fn fade_out(samples: &mut [f32], len: usize) {
+    let len = len.min(samples.len());
+    let start = samples.len() - len;
+    for (i, sample) in samples[start..].iter_mut().enumerate() {
+        *sample *= (len - i) as f32 / len as f32;
+    }
+}
+
+/// Overlap-add `tail` (fading out) onto the start of `head` (fading in).
+/// Linear gains keep the sum from exceeding either input's peak.
+fn crossfade_into(tail: &[f32], head: &mut [f32]) {
+    let len = tail.len().min(head.len());
+    for i in 0..len {
+        let t = (i as f32 + 0.5) / len as f32;
+        head[i] = tail[i] * (1.0 - t) + head[i] * t;
+    }
+}
+
+/// Optional duration predictor output holding one duration per text token
+const TOKEN_DURATION_OUTPUT: &str = "token_durations";
+
+#[derive(Clone)]
 pub struct Style {
     pub ttl: Array3<f32>,
     pub dp: Array3<f32>,
 }
 
+impl Style {
+    /// The (single) style stacked `n` times, for batching `n` texts
+    pub fn repeat(&self, n: usize) -> Result<Style> {
+        let ttl_views = vec![self.ttl.view(); n];
+        let dp_views = vec![self.dp.view(); n];
+        Ok(Style {
+            ttl: ndarray::concatenate(Axis(0), &ttl_views)?,
+            dp: ndarray::concatenate(Axis(0), &dp_views)?,
+        })
+    }
+}
+
+/// Output of one `_infer` call on a batch
+struct Inference {
+    wav: Vec<f32>,
+    /// Predicted duration of each row (seconds)
+    duration: Vec<f32>,
+    /// Per-row token durations, if the duration model exports them
+    token_durations: Option<Vec<Vec<f32>>>,
+    /// Each row as it was tokenized, after the lexicon and `preprocess_text`
+    texts: Vec<String>,
+}
+
+/// Model output for one chunk, waiting to be joined into the stream
+struct InferredChunk {
+    /// Speech cut to the predicted duration
+    speech: Vec<f32>,
+    /// Predicted duration (seconds)
+    duration: f32,
+    token_durations: Option<Vec<f32>>,
+    /// Text as tokenized, for word timings
+    text: String,
+}
+
+/// Wall-clock seconds spent in each model, summed over inference calls
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTimings {
+    pub duration_predictor: f64,
+    pub text_encoder: f64,
+    /// One entry per denoising step
+    pub vector_estimator: Vec<f64>,
+    pub vocoder: f64,
+    /// Model runs (batches of chunks) included
+    pub inferences: usize,
+}
+
+impl StageTimings {
+    pub fn vector_estimator_total(&self) -> f64 {
+        self.vector_estimator.iter().sum()
+    }
+
+    pub fn total(&self) -> f64 {
+        self.duration_predictor + self.text_encoder + self.vector_estimator_total() + self.vocoder
+    }
+}
+
 pub struct TextToSpeech {
     cfgs: Config,
     text_processor: UnicodeProcessor,
@@ -558,6 +805,13 @@ pub struct TextToSpeech {
     vector_est_ort: Session,
     vocoder_ort: Session,
     pub sample_rate: i32,
+    // Where the sessions came from, so they can be rebuilt with other options
+    onnx_dir: Option<String>,
+    engine_options: EngineOptions,
+    thread_budget: Option<Arc<AtomicUsize>>,
+    lexicon: Lexicon,
+    style_shape: StyleShape,
+    stage_timings: StageTimings,
 }
 
 impl TextToSpeech {
@@ -570,6 +824,7 @@ impl TextToSpeech {
         vocoder_ort: Session,
     ) -> Self {
         let sample_rate = cfgs.ae.sample_rate;
+        let style_shape = StyleShape::from_sessions(&dp_ort, &text_enc_ort);
         TextToSpeech {
             cfgs,
             text_processor,
@@ -578,21 +833,161 @@ impl TextToSpeech {
             vector_est_ort,
             vocoder_ort,
             sample_rate,
+            onnx_dir: None,
+            engine_options: EngineOptions::default(),
+            thread_budget: None,
+            lexicon: Lexicon::default(),
+            style_shape,
+            stage_timings: StageTimings::default(),
+        }
+    }
+
+    /// Rate of the audio returned for `options`
+    pub fn output_sample_rate(&self, options: &SynthesisOptions) -> i32 {
+        options.output_sample_rate.map_or(self.sample_rate, |rate| rate as i32)
+    }
+
+    /// Style input dimensions declared by the loaded models
+    pub fn style_shape(&self) -> StyleShape {
+        self.style_shape
+    }
+
+    /// Check that `style` fits the loaded models, so a mismatched voice fails
+    /// with a clear message instead of an ONNX Runtime shape error
+    pub fn validate_style(&self, style: &Style) -> Result<()> {
+        self.style_shape.check(style)
+    }
+
+    /// Time spent in each model since the last call (or since loading)
+    pub fn take_stage_timings(&mut self) -> StageTimings {
+        std::mem::take(&mut self.stage_timings)
+    }
+
+    /// Replace the pronunciation lexicon applied to all text before tokenization
+    pub fn set_lexicon(&mut self, lexicon: Lexicon) {
+        self.lexicon = lexicon;
+    }
+
+    /// The options the sessions were built with
+    pub fn engine_options(&self) -> &EngineOptions {
+        &self.engine_options
+    }
+
+    /// Rebuild the sessions with `n` intra-op threads as the shared default.
+    /// Models with their own `intra_threads` in `EngineOptions` keep it.
+    pub fn set_intra_threads(&mut self, n: usize) -> Result<()> {
+        let mut options = self.engine_options.clone();
+        options.session.intra_threads = Some(n.max(1));
+        self.set_engine_options(&options)
+    }
+
+    /// Rebuild the sessions whose effective options change under `options`.
+    /// Models are rebuilt and swapped one at a time so peak memory grows by a
+    /// single model; if one fails, those already swapped are rebuilt with the
+    /// previous options.
+    pub fn set_engine_options(&mut self, options: &EngineOptions) -> Result<()> {
+        if *options == self.engine_options {
+            return Ok(());
+        }
+
+        let onnx_dir = self.onnx_dir.clone()
+            .context("Cannot rebuild sessions: engine was not loaded from a model directory")?;
+        let build = |options: &EngineOptions, model: OnnxModel| {
+            options.for_model(model).build_session(&format!("{}/{}", onnx_dir, model.file_name()))
+        };
+
+        let previous = self.engine_options.clone();
+        let mut swapped = Vec::new();
+        for model in OnnxModel::ALL {
+            if options.for_model(model) == previous.for_model(model) {
+                continue;
+            }
+            match build(options, model) {
+                Ok(session) => {
+                    *self.session_mut(model) = session;
+                    swapped.push(model);
+                }
+                Err(e) => {
+                    for model in swapped {
+                        match build(&previous, model) {
+                            Ok(session) => *self.session_mut(model) = session,
+                            Err(e) => log::error!("Failed to restore the {:?} session: {:#}", model, e),
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        self.engine_options = options.clone();
+
+        log::info!(
+            "Rebuilt {} ONNX sessions (shared intra-op threads: {:?})",
+            swapped.len(),
+            options.session.intra_threads
+        );
+        Ok(())
+    }
+
+    fn session_mut(&mut self, model: OnnxModel) -> &mut Session {
+        match model {
+            OnnxModel::DurationPredictor => &mut self.dp_ort,
+            OnnxModel::TextEncoder => &mut self.text_enc_ort,
+            OnnxModel::VectorEstimator => &mut self.vector_est_ort,
+            OnnxModel::Vocoder => &mut self.vocoder_ort,
+        }
+    }
+
+    /// Follow a thread count published elsewhere (e.g. by the thermal manager).
+    /// Changes are picked up before the next inference, never during one.
+    pub fn set_thread_budget(&mut self, budget: Arc<AtomicUsize>) {
+        self.thread_budget = Some(budget);
+    }
+
+    fn apply_thread_budget(&mut self) -> Result<()> {
+        let n = match &self.thread_budget {
+            Some(budget) => budget.load(Ordering::Relaxed),
+            None => return Ok(()),
+        };
+        if n > 0 {
+            self.set_intra_threads(n)?;
         }
+        Ok(())
     }
 
+    /// Run the full pipeline on a batch. `seed` (if any) is the seed of the
+    /// first row; see `sample_noisy_latent`.
     fn _infer(
         &mut self,
         text_list: &[String],
         lang_list: &[String],
         style: &Style,
-        total_step: usize,
-        speed: f32,
-    ) -> Result<(Vec<f32>, Vec<f32>)> {
+        options: &SynthesisOptions,
+        seed: Option<u64>,
+    ) -> Result<Inference> {
+        if text_list.is_empty() {
+            anyhow::bail!("No texts to synthesize");
+        }
+        self.apply_thread_budget()?;
+
         let bsz = text_list.len();
+        let total_step = options.total_step;
+
+        self.validate_style(style)?;
+        if style.ttl.dim().0 != bsz || style.dp.dim().0 != bsz {
+            anyhow::bail!(
+                "Style batch size {} does not match the number of texts ({})",
+                style.ttl.dim().0,
+                bsz
+            );
+        }
 
         // Process text
-        let (text_ids, text_mask) = self.text_processor.call(text_list, lang_list)?;
+        let texts = text_list
+            .iter()
+            .zip(lang_list)
+            .map(|(text, lang)| preprocess_text(&self.lexicon.apply(text, lang), lang))
+            .collect::<Result<Vec<String>>>()?;
+        let (text_ids, text_mask) = self.text_processor.encode(&texts);
         
         let text_ids_array = {
             let text_ids_shape = (bsz, text_ids[0].len());
@@ -608,27 +1003,41 @@ impl TextToSpeech {
         let style_dp_value = Value::from_array(style.dp.clone())?;
 
         // Predict duration
+        let start = Instant::now();
         let dp_outputs = self.dp_ort.run(ort::inputs!{
             "text_ids" => &text_ids_value,
             "style_dp" => &style_dp_value,
             "text_mask" => &text_mask_value
         })?;
+        self.stage_timings.duration_predictor += start.elapsed().as_secs_f64();
 
         let (_, duration_data) = dp_outputs["duration"].try_extract_tensor::<f32>()?;
         let mut duration: Vec<f32> = duration_data.to_vec();
         
         // Apply speed factor to duration
         for dur in duration.iter_mut() {
-            *dur /= speed;
+            *dur /= options.speed;
         }
 
+        // Only relative token lengths matter, so no speed scaling here
+        let token_durations = match dp_outputs.get(TOKEN_DURATION_OUTPUT) {
+            Some(value) => {
+                let (shape, data) = value.try_extract_tensor::<f32>()?;
+                let row_len = shape.last().map_or(0, |&d| d as usize).max(1);
+                Some(data.chunks(row_len).map(|row| row.to_vec()).collect())
+            }
+            None => None,
+        };
+
         // Encode text
         let style_ttl_value = Value::from_array(style.ttl.clone())?;
+        let start = Instant::now();
         let text_enc_outputs = self.text_enc_ort.run(ort::inputs!{
             "text_ids" => &text_ids_value,
             "style_ttl" => &style_ttl_value,
             "text_mask" => &text_mask_value
         })?;
+        self.stage_timings.text_encoder += start.elapsed().as_secs_f64();
 
         let (text_emb_shape, text_emb_data) = text_enc_outputs["text_emb"].try_extract_tensor::<f32>()?;
         let text_emb = Array3::from_shape_vec(
@@ -643,12 +1052,17 @@ impl TextToSpeech {
             self.cfgs.ae.base_chunk_size,
             self.cfgs.ttl.chunk_compress_factor,
             self.cfgs.ttl.latent_dim,
-        );
+            options.temperature,
+            seed,
+        )?;
 
         // Prepare constant arrays
         let total_step_array = Array::from_elem(bsz, total_step as f32);
 
         // Denoising loop
+        if self.stage_timings.vector_estimator.len() < total_step {
+            self.stage_timings.vector_estimator.resize(total_step, 0.0);
+        }
         for step in 0..total_step {
             let current_step_array = Array::from_elem(bsz, step as f32);
 
@@ -659,6 +1073,7 @@ impl TextToSpeech {
             let current_step_value = Value::from_array(current_step_array)?;
             let total_step_value = Value::from_array(total_step_array.clone())?;
 
+            let start = Instant::now();
             let vector_est_outputs = self.vector_est_ort.run(ort::inputs!{
                 "noisy_latent" => &xt_value,
                 "text_emb" => &text_emb_value,
@@ -668,6 +1083,7 @@ impl TextToSpeech {
                 "current_step" => &current_step_value,
                 "total_step" => &total_step_value
             })?;
+            self.stage_timings.vector_estimator[step] += start.elapsed().as_secs_f64();
 
             let (denoised_shape, denoised_data) = vector_est_outputs["denoised_latent"].try_extract_tensor::<f32>()?;
             xt = Array3::from_shape_vec(
@@ -678,14 +1094,43 @@ impl TextToSpeech {
 
         // Generate waveform
         let final_latent_value = Value::from_array(xt)?;
+        let start = Instant::now();
         let vocoder_outputs = self.vocoder_ort.run(ort::inputs!{
             "latent" => &final_latent_value
         })?;
+        self.stage_timings.vocoder += start.elapsed().as_secs_f64();
+        self.stage_timings.inferences += 1;
 
         let (_, wav_data) = vocoder_outputs["wav_tts"].try_extract_tensor::<f32>()?;
         let wav: Vec<f32> = wav_data.to_vec();
 
-        Ok((wav, duration))
+        Ok(Inference { wav, duration, token_durations, texts })
+    }
+
+    /// Create a pull-based stream that synthesizes one chunk per `next()` call
+    pub fn stream<'a>(
+        &'a mut self,
+        text: &str,
+        lang: &str,
+        style: &'a Style,
+        options: &SynthesisOptions,
+    ) -> SynthesisStream<'a> {
+        let chunks = split_text_chunks(text, Some(chunk_max_len(lang)));
+
+        SynthesisStream {
+            tts: self,
+            aligner: TextAligner::new(text),
+            chunks,
+            next_chunk: 0,
+            lang: lang.to_string(),
+            style,
+            options: options.clone(),
+            elapsed: 0.0,
+            pending_tail: Vec::new(),
+            resampler: None,
+            inferred: VecDeque::new(),
+            running_gain_db: None,
+        }
     }
 
     pub fn call<F>(
@@ -693,71 +1138,570 @@ impl TextToSpeech {
         text: &str,
         lang: &str,
         style: &Style,
-        total_step: usize,
-        speed: f32,
-        silence_duration: f32,
-        mut callback: F,
+        options: &SynthesisOptions,
+        callback: F,
     ) -> Result<(Vec<f32>, f32)> 
     where F: FnMut(usize, usize, Option<&[f32]>) -> bool {
-        let max_len = if lang == "ko" { 120 } else { 300 };
-        let chunks = chunk_text(text, Some(max_len));
-        let num_chunks = chunks.len();
+        let (wav, duration, _) = self.call_with_timing(text, lang, style, options, callback)?;
+        Ok((wav, duration))
+    }
+
+    /// Like `call`, additionally returning where each chunk and word of `text`
+    /// is spoken
+    pub fn call_with_timing<F>(
+        &mut self,
+        text: &str,
+        lang: &str,
+        style: &Style,
+        options: &SynthesisOptions,
+        mut callback: F,
+    ) -> Result<(Vec<f32>, f32, Vec<ChunkTiming>)> 
+    where F: FnMut(usize, usize, Option<&[f32]>) -> bool {
+        let mut stream = self.stream(text, lang, style, options);
+        let num_chunks = stream.num_chunks();
         
         let mut wav_cat: Vec<f32> = Vec::new();
         let mut dur_cat: f32 = 0.0;
+        let mut timings: Vec<ChunkTiming> = Vec::new();
 
-        for (i, chunk) in chunks.iter().enumerate() {
+        while !stream.is_finished() {
             // Notify start of chunk (audio is None)
-            if !callback(i, num_chunks, None) {
+            if !callback(stream.next_chunk, num_chunks, None) {
                 return Err(anyhow::anyhow!("Synthesis cancelled by user"));
             }
-            
-            let (wav, duration) = self._infer(&[chunk.clone()], &[lang.to_string()], style, total_step, speed)?;
-
-            // Truncate audio based on predicted duration to remove trailing silence
-            let dur = duration[0];
-            let sample_count = (dur * self.sample_rate as f32) as usize;
-            let wav_chunk = if sample_count < wav.len() {
-                &wav[..sample_count]
-            } else {
-                &wav[..]
+
+            let frame = match stream.next() {
+                Some(frame) => frame?,
+                None => break,
             };
 
             // Send audio chunk
-            if !callback(i, num_chunks, Some(wav_chunk)) {
+            if !callback(frame.chunk_index, frame.num_chunks, Some(frame.speech())) {
                  return Err(anyhow::anyhow!("Synthesis cancelled by user"));
             }
 
-            if i == 0 {
-                wav_cat.extend_from_slice(wav_chunk);
-                dur_cat = dur;
-            } else {
-                let silence_len = (silence_duration * self.sample_rate as f32) as usize;
-                let silence = vec![0.0f32; silence_len];
-                
-                wav_cat.extend_from_slice(&silence);
-                wav_cat.extend_from_slice(wav_chunk);
-                dur_cat += silence_duration + dur;
+            wav_cat.extend_from_slice(&frame.samples);
+            dur_cat = frame.end();
+            timings.push(ChunkTiming {
+                start: frame.start,
+                end: frame.end(),
+                text: frame.text,
+                words: frame.words,
+            });
+        }
+        callback(num_chunks, num_chunks, None);
+
+        Ok((wav_cat, dur_cat, timings))
+    }
+
+    /// Synthesize an SSML document (see `ssml::parse_ssml` for the supported
+    /// subset). `resolve_voice` loads the style for every `<voice name>` used;
+    /// text outside `<voice>` uses `style`.
+    pub fn call_ssml<V, F>(
+        &mut self,
+        ssml: &str,
+        lang: &str,
+        style: &Style,
+        options: &SynthesisOptions,
+        mut resolve_voice: V,
+        mut callback: F,
+    ) -> Result<(Vec<f32>, f32)>
+    where
+        V: FnMut(&str) -> Result<Style>,
+        F: FnMut(usize, usize, Option<&[f32]>) -> bool,
+    {
+        let doc = parse_ssml(ssml, lang)?;
+
+        let mut voices: HashMap<String, Style> = HashMap::new();
+        for name in doc.segments.iter().filter_map(|s| s.voice.as_ref()) {
+            if !voices.contains_key(name) {
+                let voice = resolve_voice(name)
+                    .with_context(|| format!("Failed to load SSML voice: {}", name))?;
+                voices.insert(name.clone(), voice);
             }
         }
+
+        let num_chunks: usize = doc
+            .segments
+            .iter()
+            .map(|s| split_text_chunks(&s.text, Some(chunk_max_len(&s.lang))).len())
+            .sum();
+        let sample_rate = self.output_sample_rate(options) as f32;
+        let mut wav_cat: Vec<f32> = Vec::new();
+        let mut chunk_offset = 0;
+
+        for (i, segment) in doc.segments.iter().enumerate() {
+            // Voice, language and rate changes inside a sentence only pause
+            // where a <break> asks for it
+            let default_pause = match segment.boundary {
+                _ if i == 0 => 0.0,
+                SsmlBoundary::Paragraph => options.join.paragraph_pause,
+                SsmlBoundary::Sentence => options.join.sentence_pause,
+                SsmlBoundary::Inline => 0.0,
+            };
+            let pause = segment.pause_before.unwrap_or(default_pause);
+            wav_cat.resize(wav_cat.len() + (pause * sample_rate) as usize, 0.0);
+
+            let segment_style = match &segment.voice {
+                Some(name) => &voices[name],
+                None => style,
+            };
+            let segment_options = SynthesisOptions {
+                speed: options.speed * segment.rate,
+                // Keep per-chunk seeds distinct across segments
+                seed: options.seed.map(|seed| seed.wrapping_add(chunk_offset as u64)),
+                ..options.clone()
+            };
+
+            let stream = self.stream(&segment.text, &segment.lang, segment_style, &segment_options);
+            for frame in stream {
+                if !callback(chunk_offset, num_chunks, None) {
+                    return Err(anyhow::anyhow!("Synthesis cancelled by user"));
+                }
+                let frame = frame?;
+                if !callback(chunk_offset, num_chunks, Some(frame.speech())) {
+                    return Err(anyhow::anyhow!("Synthesis cancelled by user"));
+                }
+                wav_cat.extend_from_slice(&frame.samples);
+                chunk_offset += 1;
+            }
+        }
+        wav_cat.resize(wav_cat.len() + (doc.trailing_pause * sample_rate) as usize, 0.0);
         callback(num_chunks, num_chunks, None);
 
-        Ok((wav_cat, dur_cat))
+        let duration = wav_cat.len() as f32 / sample_rate;
+        Ok((wav_cat, duration))
     }
 
-    #[allow(dead_code)]
     pub fn batch(
         &mut self,
         text_list: &[String],
         lang_list: &[String],
         style: &Style,
-        total_step: usize,
-        speed: f32,
+        options: &SynthesisOptions,
     ) -> Result<(Vec<f32>, Vec<f32>)> {
-        self._infer(text_list, lang_list, style, total_step, speed)
+        let Inference { wav, duration, .. } = self._infer(text_list, lang_list, style, options, options.seed)?;
+        let output_rate = self.output_sample_rate(options);
+        if output_rate == self.sample_rate || wav.is_empty() {
+            return Ok((wav, duration));
+        }
+        // Every item is padded to the same length; resample them separately
+        let item_len = wav.len() / text_list.len();
+        let mut output = Vec::new();
+        for item in wav.chunks(item_len) {
+            output.extend(resample(item, self.sample_rate as u32, output_rate as u32)?);
+        }
+        Ok((output, duration))
+    }
+}
+
+// ============================================================================
+// Streaming Synthesis
+// ============================================================================
+
+/// Where a chunk (as produced by `chunk_text`) is spoken in the overall output
+#[derive(Debug, Clone, Serialize)]
+pub struct ChunkTiming {
+    pub text: String,
+    pub start: f32,
+    pub end: f32,
+    pub words: Vec<WordTiming>,
+}
+
+/// PCM audio for a single synthesized chunk
+pub struct AudioFrame {
+    pub chunk_index: usize,
+    pub num_chunks: usize,
+    /// Chunk text as produced by `chunk_text`
+    pub text: String,
+    /// Samples for this chunk, starting with the inter-chunk silence (if any).
+    /// When the next chunk follows without a pause, the end of the speech is
+    /// held back and crossfaded into the next frame.
+    pub samples: Vec<f32>,
+    /// Index into `samples` where speech begins
+    pub speech_offset: usize,
+    /// Position of the first speech sample in the overall output (seconds)
+    pub start: f32,
+    /// Predicted speech duration of this chunk (seconds)
+    pub duration: f32,
+    /// Words of this chunk with absolute times in the overall output
+    pub words: Vec<WordTiming>,
+}
+
+impl AudioFrame {
+    /// Speech samples without the leading silence
+    pub fn speech(&self) -> &[f32] {
+        &self.samples[self.speech_offset..]
+    }
+
+    /// Position right after the last speech sample in the overall output (seconds)
+    pub fn end(&self) -> f32 {
+        self.start + self.duration
+    }
+}
+
+/// Iterator yielding one `AudioFrame` per text chunk.
+///
+/// Nothing is synthesized ahead of the consumer: chunks are run through the
+/// models only when `next()` needs them, and no audio is retained once the
+/// frame has been handed out, so memory stays bounded by one batch of chunks
+/// (`SynthesisOptions::chunk_batch_size`, one chunk by default).
+pub struct SynthesisStream<'a> {
+    tts: &'a mut TextToSpeech,
+    aligner: TextAligner,
+    chunks: Vec<TextChunk>,
+    next_chunk: usize,
+    lang: String,
+    style: &'a Style,
+    options: SynthesisOptions,
+    elapsed: f32,
+    /// End of the previous chunk, held back to be crossfaded into this one
+    pending_tail: Vec<f32>,
+    /// Converts frames to `options.output_sample_rate`, carrying filter state
+    /// across chunk boundaries
+    resampler: Option<Resampler>,
+    /// Chunks already synthesized as part of a batch, in order
+    inferred: VecDeque<InferredChunk>,
+    /// Loudness gain last measured on a chunk, applied to chunks too short
+    /// to measure
+    running_gain_db: Option<f32>,
+}
+
+impl SynthesisStream<'_> {
+    pub fn num_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next_chunk >= self.chunks.len()
+    }
+
+    /// Sample rate of the produced frames
+    pub fn sample_rate(&self) -> i32 {
+        self.tts.output_sample_rate(&self.options)
+    }
+
+    /// Convert a frame to the output rate; `speech_offset` is mapped to the
+    /// first output sample at or after the start of speech
+    fn resample_frame(&mut self, samples: Vec<f32>, speech_offset: usize, last: bool) -> Result<(Vec<f32>, usize)> {
+        let from = self.tts.sample_rate as u32;
+        let to = self.tts.output_sample_rate(&self.options) as u32;
+        if from == to {
+            return Ok((samples, speech_offset));
+        }
+        if self.resampler.is_none() {
+            self.resampler = Some(Resampler::new(from, to)?);
+        }
+        let resampler = self.resampler.as_mut().unwrap();
+
+        let (consumed, produced) = (resampler.consumed(), resampler.produced());
+        let speech_start = resampler.output_position(consumed + speech_offset as u64) - produced;
+        let mut output = resampler.process(&samples);
+        if last {
+            output.extend(resampler.flush());
+        }
+        let speech_offset = (speech_start as usize).min(output.len());
+        Ok((output, speech_offset))
+    }
+
+    /// Run chunk `i` and, with `chunk_batch_size > 1`, the chunks after it
+    /// through the models in one batch
+    fn infer_chunks(&mut self, i: usize) -> Result<()> {
+        let sample_rate = self.tts.sample_rate;
+        let batch_size = match i {
+            0 => 1,
+            _ => self.options.chunk_batch_size.max(1),
+        };
+        let end = (i + batch_size).min(self.chunks.len());
+        let texts: Vec<String> = self.chunks[i..end].iter().map(|c| c.text.clone()).collect();
+        let langs = vec![self.lang.clone(); texts.len()];
+        let style = match texts.len() {
+            1 => self.style.clone(),
+            n => self.style.repeat(n)?,
+        };
+
+        let Inference { wav, duration, token_durations, texts: processed } = self.tts._infer(
+            &texts,
+            &langs,
+            &style,
+            &self.options,
+            // Chunk i always gets the same noise for a given seed (row b of
+            // the batch is seeded with seed + b)
+            self.options.seed.map(|seed| seed.wrapping_add(i as u64)),
+        )?;
+
+        // Truncate audio based on predicted duration to remove trailing silence
+        let row_len = wav.len() / texts.len();
+        for (b, (&predicted, text)) in duration.iter().zip(processed).enumerate() {
+            let row = &wav[b * row_len..(b + 1) * row_len];
+            let sample_count = ((predicted * sample_rate as f32) as usize).min(row.len());
+            self.inferred.push_back(InferredChunk {
+                speech: row[..sample_count].to_vec(),
+                duration: predicted,
+                token_durations: token_durations.as_ref().and_then(|rows| rows.get(b).cloned()),
+                text,
+            });
+        }
+        Ok(())
+    }
+
+    fn synthesize_chunk(&mut self, i: usize) -> Result<AudioFrame> {
+        let sample_rate = self.tts.sample_rate;
+        if self.inferred.is_empty() {
+            self.infer_chunks(i)?;
+        }
+        let InferredChunk { mut speech, duration: predicted, token_durations, text: processed } = self
+            .inferred
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("No audio for chunk {}", i))?;
+
+        // Per-chunk post-processing; trimming shifts where the words are heard
+        let (dur, trimmed_lead) = if self.options.chunk_postprocess.is_noop() {
+            (predicted, 0.0)
+        } else {
+            let report = self.options.chunk_postprocess.apply_with_fallback_gain(
+                &mut speech,
+                sample_rate,
+                self.running_gain_db,
+            );
+            if report.measured {
+                self.running_gain_db = Some(report.gain_db);
+            }
+            (
+                speech.len() as f32 / sample_rate as f32,
+                report.trimmed_start as f32 / sample_rate as f32,
+            )
+        };
+
+        let join = &self.options.join;
+        let fade_len = (join.fade.max(0.0) * sample_rate as f32) as usize;
+        let crossfade_len = (join.crossfade.max(0.0) * sample_rate as f32) as usize;
+        let pause_before = match i {
+            0 => 0.0,
+            _ => join.pause_after(self.chunks[i - 1].boundary),
+        };
+        let pause_after = match self.chunks.get(i + 1) {
+            Some(_) => Some(join.pause_after(self.chunks[i].boundary)),
+            None => None,
+        };
+
+        // Head: crossfade with the tail held back from the previous chunk,
+        // otherwise fade in after a pause (or at the very start)
+        let tail = std::mem::take(&mut self.pending_tail);
+        let mut samples = Vec::new();
+        let silence_len;
+        if tail.is_empty() {
+            if i == 0 || pause_before > 0.0 {
+                fade_in(&mut speech, fade_len);
+            }
+            silence_len = (pause_before * sample_rate as f32) as usize;
+            self.elapsed += pause_before;
+            samples.resize(silence_len, 0.0);
+        } else {
+            let overlap = tail.len().min(speech.len());
+            samples.extend_from_slice(&tail[..tail.len() - overlap]);
+            crossfade_into(&tail[tail.len() - overlap..], &mut speech);
+            silence_len = 0;
+            // Only the overlapping part moves this chunk earlier
+            self.elapsed -= overlap as f32 / sample_rate as f32;
+        }
+
+        // Tail: hold it back for a crossfade when the next chunk follows
+        // without a pause, otherwise fade out
+        match pause_after {
+            Some(pause) if pause <= 0.0 && crossfade_len > 0 => {
+                let held = crossfade_len.min(speech.len() / 2);
+                self.pending_tail = speech.split_off(speech.len() - held);
+            }
+            Some(pause) if pause <= 0.0 => {}
+            _ => fade_out(&mut speech, fade_len),
+        }
+        samples.extend_from_slice(&speech);
+
+        let start = self.elapsed;
+        self.elapsed += dur;
+
+        let (samples, silence_len) = self.resample_frame(samples, silence_len, i + 1 == self.chunks.len())?;
+
+        // Rows of a batch are padded to its longest text
+        let token_count = processed.chars().count();
+        let token_durations = token_durations.as_deref().map(|t| &t[..token_count.min(t.len())]);
+        let spans = estimate_word_spans(&processed, predicted, token_durations);
+        let words = self.aligner.align(spans, start - trimmed_lead);
+
+        Ok(AudioFrame {
+            chunk_index: i,
+            num_chunks: self.chunks.len(),
+            text: self.chunks[i].text.clone(),
+            samples,
+            speech_offset: silence_len,
+            start,
+            duration: dur,
+            words,
+        })
     }
 }
 
+impl Iterator for SynthesisStream<'_> {
+    type Item = Result<AudioFrame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_finished() {
+            return None;
+        }
+
+        let i = self.next_chunk;
+        self.next_chunk += 1;
+
+        let frame = self.synthesize_chunk(i);
+        if frame.is_err() {
+            // Stop after the first failure
+            self.next_chunk = self.chunks.len();
+        }
+        Some(frame)
+    }
+}
+
+// ============================================================================
+// ONNX Runtime Session Options
+// ============================================================================
+
+/// The four models making up the pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OnnxModel {
+    DurationPredictor,
+    TextEncoder,
+    VectorEstimator,
+    Vocoder,
+}
+
+impl OnnxModel {
+    pub const ALL: [OnnxModel; 4] = [
+        OnnxModel::DurationPredictor,
+        OnnxModel::TextEncoder,
+        OnnxModel::VectorEstimator,
+        OnnxModel::Vocoder,
+    ];
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            OnnxModel::DurationPredictor => "duration_predictor.onnx",
+            OnnxModel::TextEncoder => "text_encoder.onnx",
+            OnnxModel::VectorEstimator => "vector_estimator.onnx",
+            OnnxModel::Vocoder => "vocoder.onnx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OptimizationLevel {
+    Disable,
+    Basic,
+    Extended,
+    All,
+}
+
+impl From<OptimizationLevel> for GraphOptimizationLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::Disable => GraphOptimizationLevel::Disable,
+            OptimizationLevel::Basic => GraphOptimizationLevel::Level1,
+            OptimizationLevel::Extended => GraphOptimizationLevel::Level2,
+            OptimizationLevel::All => GraphOptimizationLevel::Level3,
+        }
+    }
+}
+
+/// Options for a single ORT session. Unset fields keep ORT's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionOptions {
+    pub intra_threads: Option<usize>,
+    pub inter_threads: Option<usize>,
+    pub parallel_execution: Option<bool>,
+    pub optimization_level: Option<OptimizationLevel>,
+    pub memory_pattern: Option<bool>,
+    pub cpu_arena: Option<bool>,
+}
+
+impl SessionOptions {
+    /// Fields set in `self` win, the rest are taken from `base`
+    pub fn or(&self, base: &SessionOptions) -> SessionOptions {
+        SessionOptions {
+            intra_threads: self.intra_threads.or(base.intra_threads),
+            inter_threads: self.inter_threads.or(base.inter_threads),
+            parallel_execution: self.parallel_execution.or(base.parallel_execution),
+            optimization_level: self.optimization_level.or(base.optimization_level),
+            memory_pattern: self.memory_pattern.or(base.memory_pattern),
+            cpu_arena: self.cpu_arena.or(base.cpu_arena),
+        }
+    }
+
+    fn build_session(&self, model_path: &str) -> Result<Session> {
+        let mut builder = Session::builder()?;
+
+        if let Some(n) = self.intra_threads {
+            builder = builder.with_intra_threads(n)?;
+        }
+        if let Some(n) = self.inter_threads {
+            builder = builder.with_inter_threads(n)?;
+        }
+        if let Some(enable) = self.parallel_execution {
+            builder = builder.with_parallel_execution(enable)?;
+        }
+        if let Some(level) = self.optimization_level {
+            builder = builder.with_optimization_level(level.into())?;
+        }
+        if let Some(enable) = self.memory_pattern {
+            builder = builder.with_memory_pattern(enable)?;
+        }
+        if let Some(enable) = self.cpu_arena {
+            builder = builder.with_execution_providers([
+                CPUExecutionProvider::default().with_arena_allocator(enable).build(),
+            ])?;
+        }
+
+        builder
+            .commit_from_file(model_path)
+            .with_context(|| format!("Failed to load ONNX model {}", model_path))
+    }
+}
+
+/// Session options for the whole engine: shared defaults plus per-model
+/// overrides. Deserializable from JSON, e.g.
+/// `{"session": {"intra_threads": 4}, "vocoder": {"intra_threads": 2}}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EngineOptions {
+    pub session: SessionOptions,
+    pub duration_predictor: SessionOptions,
+    pub text_encoder: SessionOptions,
+    pub vector_estimator: SessionOptions,
+    pub vocoder: SessionOptions,
+}
+
+impl EngineOptions {
+    /// Effective options for `model` after applying its overrides
+    pub fn for_model(&self, model: OnnxModel) -> SessionOptions {
+        let overrides = match model {
+            OnnxModel::DurationPredictor => &self.duration_predictor,
+            OnnxModel::TextEncoder => &self.text_encoder,
+            OnnxModel::VectorEstimator => &self.vector_estimator,
+            OnnxModel::Vocoder => &self.vocoder,
+        };
+        overrides.or(&self.session)
+    }
+}
+
+pub fn load_engine_options<P: AsRef<Path>>(path: P) -> Result<EngineOptions> {
+    let file = File::open(path.as_ref())
+        .with_context(|| format!("Failed to open engine options {}", path.as_ref().display()))?;
+    let reader = BufReader::new(file);
+    let options: EngineOptions = serde_json::from_reader(reader)?;
+    Ok(options)
+}
+
 // ============================================================================ 
 // Component Loading Functions
 // ============================================================================ 
@@ -765,63 +1709,44 @@ impl TextToSpeech {
 /// Load voice style from JSON files
 pub fn load_voice_style(voice_style_paths: &[String], verbose: bool) -> Result<Style> {
     let bsz = voice_style_paths.len();
+    if bsz == 0 {
+        anyhow::bail!("No voice style paths given");
+    }
 
     // Read first file to get dimensions
-    let first_file = File::open(&voice_style_paths[0])
-        .context("Failed to open voice style file")?;
-    let first_reader = BufReader::new(first_file);
-    let first_data: VoiceStyleData = serde_json::from_reader(first_reader)?;
-
-    let ttl_dims = &first_data.style_ttl.dims;
-    let dp_dims = &first_data.style_dp.dims;
-
-    let ttl_dim1 = ttl_dims[1];
-    let ttl_dim2 = ttl_dims[2];
-    let dp_dim1 = dp_dims[1];
-    let dp_dim2 = dp_dims[2];
+    let (first_ttl, first_dp) = read_style_tensors(&voice_style_paths[0])?;
+    let (_, ttl_dim1, ttl_dim2) = first_ttl.dim();
+    let (_, dp_dim1, dp_dim2) = first_dp.dim();
 
     // Pre-allocate arrays with full batch size
-    let ttl_size = bsz * ttl_dim1 * ttl_dim2;
-    let dp_size = bsz * dp_dim1 * dp_dim2;
-    let mut ttl_flat = vec![0.0f32; ttl_size];
-    let mut dp_flat = vec![0.0f32; dp_size];
+    let mut ttl_flat = Vec::with_capacity(bsz * ttl_dim1 * ttl_dim2);
+    let mut dp_flat = Vec::with_capacity(bsz * dp_dim1 * dp_dim2);
+    ttl_flat.extend(first_ttl.iter());
+    dp_flat.extend(first_dp.iter());
 
     // Fill in the data
-    for (i, path) in voice_style_paths.iter().enumerate() {
-        let file = File::open(path).context("Failed to open voice style file")?;
-        let reader = BufReader::new(file);
-        let data: VoiceStyleData = serde_json::from_reader(reader)?;
-
-        // Flatten TTL data
-        let ttl_offset = i * ttl_dim1 * ttl_dim2;
-        let mut idx = 0;
-        for batch in &data.style_ttl.data {
-            for row in batch {
-                for &val in row {
-                    ttl_flat[ttl_offset + idx] = val;
-                    idx += 1;
-                }
-            }
-        }
-
-        // Flatten DP data
-        let dp_offset = i * dp_dim1 * dp_dim2;
-        idx = 0;
-        for batch in &data.style_dp.data {
-            for row in batch {
-                for &val in row {
-                    dp_flat[dp_offset + idx] = val;
-                    idx += 1;
-                }
-            }
+    for path in &voice_style_paths[1..] {
+        let (ttl, dp) = read_style_tensors(path)?;
+        if ttl.dim() != first_ttl.dim() || dp.dim() != first_dp.dim() {
+            anyhow::bail!(
+                "Voice style dimensions mismatch: {} has ttl {:?} / dp {:?}, {} has ttl {:?} / dp {:?}",
+                path,
+                ttl.dim(),
+                dp.dim(),
+                voice_style_paths[0],
+                first_ttl.dim(),
+                first_dp.dim()
+            );
         }
+        ttl_flat.extend(ttl.iter());
+        dp_flat.extend(dp.iter());
     }
 
     let ttl_style = Array3::from_shape_vec((bsz, ttl_dim1, ttl_dim2), ttl_flat)?;
     let dp_style = Array3::from_shape_vec((bsz, dp_dim1, dp_dim2), dp_flat)?;
 
     if verbose {
-        println!("Loaded {} voice styles\n", bsz);
+        eprintln!("Loaded {} voice styles\n", bsz);
     }
 
     Ok(Style {
@@ -830,53 +1755,526 @@ pub fn load_voice_style(voice_style_paths: &[String], verbose: bool) -> Result<S
     })
 }
 
-/// Load and mix two voice styles
-pub fn load_and_mix_voice_styles(path1: &str, path2: &str, alpha: f32) -> Result<Style> {
-    let s1 = load_voice_style(&[path1.to_string()], false)?;
-    let s2 = load_voice_style(&[path2.to_string()], false)?;
+/// Read the (ttl, dp) tensors of a single style file, JSON or binary
+fn read_style_tensors(path: &str) -> Result<(Array3<f32>, Array3<f32>)> {
+    let bytes = std::fs::read(path)
+        .with_context(|| format!("Failed to open voice style file: {}", path))?;
+    let (ttl, dp) = if bytes.starts_with(STYLE_BINARY_MAGIC) {
+        decode_style_binary(&bytes)
+            .with_context(|| format!("Invalid binary voice style: {}", path))?
+    } else {
+        let data: VoiceStyleData = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Invalid voice style JSON: {}", path))?;
+        let parse = || -> Result<_> {
+            Ok((data.style_ttl.to_array("style_ttl")?, data.style_dp.to_array("style_dp")?))
+        };
+        parse().with_context(|| format!("Invalid voice style: {}", path))?
+    };
+
+    check_style_tensor("style_ttl", &ttl)
+        .and_then(|_| check_style_tensor("style_dp", &dp))
+        .with_context(|| format!("Invalid voice style: {}", path))?;
+    Ok((ttl, dp))
+}
+
+impl StyleComponent {
+    /// Validate `dtype` and that `data` matches `dims` before flattening
+    fn to_array(&self, name: &str) -> Result<Array3<f32>> {
+        if !SUPPORTED_STYLE_DTYPES.contains(&self.dtype.as_str()) {
+            anyhow::bail!(
+                "{}: unsupported dtype '{}' (expected one of {:?})",
+                name,
+                self.dtype,
+                SUPPORTED_STYLE_DTYPES
+            );
+        }
+        let [d0, d1, d2] = <[usize; 3]>::try_from(self.dims.as_slice())
+            .map_err(|_| anyhow::anyhow!("{}: expected 3 dims, got {:?}", name, self.dims))?;
+
+        if self.data.len() != d0 {
+            anyhow::bail!("{}: dims {:?} but data has {} entries", name, self.dims, self.data.len());
+        }
+        let mut flat = Vec::with_capacity(d0 * d1 * d2);
+        for (i, rows) in self.data.iter().enumerate() {
+            if rows.len() != d1 {
+                anyhow::bail!("{}: data[{}] has {} rows, expected {}", name, i, rows.len(), d1);
+            }
+            for (j, row) in rows.iter().enumerate() {
+                if row.len() != d2 {
+                    anyhow::bail!("{}: data[{}][{}] has {} values, expected {}", name, i, j, row.len(), d2);
+                }
+                flat.extend_from_slice(row);
+            }
+        }
+        Ok(Array3::from_shape_vec((d0, d1, d2), flat)?)
+    }
+}
+
+/// `type` values accepted in JSON voice styles (values are read as f32)
+const SUPPORTED_STYLE_DTYPES: &[&str] = &["float32", "float16"];
+
+/// Checks shared by both file formats: one non-empty voice per file and no
+/// NaN/infinite values
+fn check_style_tensor(name: &str, tensor: &Array3<f32>) -> Result<()> {
+    let (d0, d1, d2) = tensor.dim();
+    if d0 != 1 {
+        anyhow::bail!("{}: a style file holds one voice, but batch dim is {}", name, d0);
+    }
+    if d1 == 0 || d2 == 0 {
+        anyhow::bail!("{}: empty tensor {:?}", name, tensor.dim());
+    }
+    if let Some(pos) = tensor.iter().position(|v| !v.is_finite()) {
+        anyhow::bail!("{}: non-finite value at flat index {}", name, pos);
+    }
+    Ok(())
+}
+
+/// Expected `(dim1, dim2)` of the style inputs of the loaded models.
+/// `None` marks a dimension the model leaves dynamic (or does not declare).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StyleShape {
+    pub ttl: [Option<usize>; 2],
+    pub dp: [Option<usize>; 2],
+}
+
+impl StyleShape {
+    fn from_sessions(dp_ort: &Session, text_enc_ort: &Session) -> Self {
+        StyleShape {
+            ttl: session_style_dims(text_enc_ort, "style_ttl"),
+            dp: session_style_dims(dp_ort, "style_dp"),
+        }
+    }
+
+    /// Check a (possibly batched) style against the model's style inputs
+    pub fn check(&self, style: &Style) -> Result<()> {
+        for (name, tensor, expected) in [("style_ttl", &style.ttl, self.ttl), ("style_dp", &style.dp, self.dp)] {
+            let (_, d1, d2) = tensor.dim();
+            for (actual_dim, expected_dim) in [(d1, expected[0]), (d2, expected[1])] {
+                if expected_dim.is_some_and(|e| e != actual_dim) {
+                    anyhow::bail!(
+                        "{} has shape {:?}, but the model expects (_, {}, {}); \
+                         the voice style was probably made for another model version",
+                        name,
+                        tensor.dim(),
+                        expected[0].map_or("?".to_string(), |d| d.to_string()),
+                        expected[1].map_or("?".to_string(), |d| d.to_string()),
+                    );
+                }
+            }
+            if let Some(pos) = tensor.iter().position(|v| !v.is_finite()) {
+                anyhow::bail!("{}: non-finite value at flat index {}", name, pos);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn session_style_dims(session: &Session, input: &str) -> [Option<usize>; 2] {
+    let shape = session
+        .inputs
+        .iter()
+        .find(|i| i.name == input)
+        .and_then(|i| i.input_type.tensor_shape());
+    match shape {
+        Some(shape) if shape.len() == 3 => {
+            let dim = |d: i64| usize::try_from(d).ok().filter(|d| *d > 0);
+            [dim(shape[1]), dim(shape[2])]
+        }
+        _ => [None, None],
+    }
+}
+
+// Binary style layout (little-endian):
+//   magic "STYL" | version u16 | dtype u8 | reserved u8 | ttl dims 3 x u32 |
+//   dp dims 3 x u32 | ttl values | dp values | CRC32 of everything before it
+pub const STYLE_BINARY_MAGIC: &[u8; 4] = b"STYL";
+pub const STYLE_BINARY_VERSION: u16 = 1;
+const STYLE_BINARY_HEADER_LEN: usize = 32;
+
+/// Element type of a binary style payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StyleDtype {
+    F32,
+    F16,
+}
+
+impl StyleDtype {
+    fn tag(self) -> u8 {
+        match self {
+            StyleDtype::F32 => 0,
+            StyleDtype::F16 => 1,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            0 => Ok(StyleDtype::F32),
+            1 => Ok(StyleDtype::F16),
+            _ => anyhow::bail!("Unknown style dtype tag {}", tag),
+        }
+    }
 
-    if s1.ttl.dim() != s2.ttl.dim() || s1.dp.dim() != s2.dp.dim() {
+    fn size(self) -> usize {
+        match self {
+            StyleDtype::F32 => 4,
+            StyleDtype::F16 => 2,
+        }
+    }
+}
+
+/// Serialize a single style (batch size 1) to the binary format
+pub fn encode_style_binary(style: &Style, dtype: StyleDtype) -> Result<Vec<u8>> {
+    if style.ttl.dim().0 != 1 || style.dp.dim().0 != 1 {
+        anyhow::bail!("Only single voice styles can be encoded");
+    }
+
+    let payload_len = (style.ttl.len() + style.dp.len()) * dtype.size();
+    let mut out = Vec::with_capacity(STYLE_BINARY_HEADER_LEN + payload_len + 4);
+    out.extend_from_slice(STYLE_BINARY_MAGIC);
+    out.extend_from_slice(&STYLE_BINARY_VERSION.to_le_bytes());
+    out.push(dtype.tag());
+    out.push(0);
+    for tensor in [&style.ttl, &style.dp] {
+        let (d0, d1, d2) = tensor.dim();
+        for d in [d0, d1, d2] {
+            out.extend_from_slice(&u32::try_from(d)?.to_le_bytes());
+        }
+    }
+
+    // Iterate in logical (row-major) order regardless of memory layout
+    for &val in style.ttl.iter().chain(style.dp.iter()) {
+        match dtype {
+            StyleDtype::F32 => out.extend_from_slice(&val.to_le_bytes()),
+            StyleDtype::F16 => out.extend_from_slice(&half::f16::from_f32(val).to_le_bytes()),
+        }
+    }
+
+    let checksum = crc32fast::hash(&out);
+    out.extend_from_slice(&checksum.to_le_bytes());
+    Ok(out)
+}
+
+/// Parse the binary format into (ttl, dp) tensors
+pub fn decode_style_binary(bytes: &[u8]) -> Result<(Array3<f32>, Array3<f32>)> {
+    if bytes.len() < STYLE_BINARY_HEADER_LEN + 4 || !bytes.starts_with(STYLE_BINARY_MAGIC) {
+        anyhow::bail!("Not a binary voice style");
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    let expected = u32::from_le_bytes(checksum.try_into()?);
+    if crc32fast::hash(body) != expected {
+        anyhow::bail!("Checksum mismatch");
+    }
+
+    let version = u16::from_le_bytes([body[4], body[5]]);
+    if version > STYLE_BINARY_VERSION {
+        anyhow::bail!("Unsupported style format version {} (max {})", version, STYLE_BINARY_VERSION);
+    }
+    let dtype = StyleDtype::from_tag(body[6])?;
+
+    let read_u32 = |offset: usize| u32::from_le_bytes(body[offset..offset + 4].try_into().unwrap()) as usize;
+    let ttl_dims = (read_u32(8), read_u32(12), read_u32(16));
+    let dp_dims = (read_u32(20), read_u32(24), read_u32(28));
+    // Dims come from the file, so a corrupt header must not overflow
+    let volume = |(d0, d1, d2): (usize, usize, usize)| d0.checked_mul(d1).and_then(|n| n.checked_mul(d2));
+    let (ttl_len, dp_len) = volume(ttl_dims)
+        .zip(volume(dp_dims))
+        .context("Header dims are too large")?;
+    let payload_len = ttl_len
+        .checked_add(dp_len)
+        .and_then(|n| n.checked_mul(dtype.size()))
+        .context("Header dims are too large")?;
+
+    let payload = &body[STYLE_BINARY_HEADER_LEN..];
+    if payload.len() != payload_len {
+        anyhow::bail!("Payload size does not match header dims");
+    }
+
+    let values: Vec<f32> = match dtype {
+        StyleDtype::F32 => payload
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect(),
+        StyleDtype::F16 => payload
+            .chunks_exact(2)
+            .map(|b| half::f16::from_le_bytes([b[0], b[1]]).to_f32())
+            .collect(),
+    };
+    let (ttl, dp) = values.split_at(ttl_len);
+
+    Ok((
+        Array3::from_shape_vec(ttl_dims, ttl.to_vec())?,
+        Array3::from_shape_vec(dp_dims, dp.to_vec())?,
+    ))
+}
+
+/// Convert a JSON (or binary) style file to the binary format
+pub fn convert_voice_style<P: AsRef<Path>>(input: &str, output: P, dtype: StyleDtype) -> Result<()> {
+    let style = load_voice_style(&[input.to_string()], false)?;
+    let bytes = encode_style_binary(&style, dtype)?;
+    std::fs::write(output.as_ref(), bytes)
+        .with_context(|| format!("Failed to write {}", output.as_ref().display()))?;
+    Ok(())
+}
+
+/// Convert style files to `.style` files in `out_dir` (default: next to each
+/// input), printing the size change of each
+pub fn convert_voice_styles(inputs: &[String], out_dir: Option<&str>, dtype: StyleDtype) -> Result<()> {
+    if let Some(dir) = out_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    for input in inputs {
+        let input_path = Path::new(input);
+        let output = match out_dir {
+            Some(dir) => Path::new(dir).join(input_path.file_name().unwrap_or_default()),
+            None => input_path.to_path_buf(),
+        }
+        .with_extension("style");
+
+        convert_voice_style(input, &output, dtype)?;
+        let before = std::fs::metadata(input)?.len();
+        let after = std::fs::metadata(&output)?.len();
+        println!("{} -> {} ({} -> {} bytes)", input, output.display(), before, after);
+    }
+    Ok(())
+}
+
+/// One voice in a blend recipe. `weight` applies to both components unless
+/// `ttlWeight` (timbre) or `dpWeight` (pacing) override it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlendComponent {
+    pub path: String,
+    #[serde(default = "default_blend_weight")]
+    pub weight: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_weight: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dp_weight: Option<f32>,
+}
+
+fn default_blend_weight() -> f32 {
+    1.0
+}
+
+impl BlendComponent {
+    pub fn ttl_weight(&self) -> f32 {
+        self.ttl_weight.unwrap_or(self.weight)
+    }
+
+    pub fn dp_weight(&self) -> f32 {
+        self.dp_weight.unwrap_or(self.weight)
+    }
+}
+
+/// Normalize weights to sum to 1
+fn normalize_blend_weights(weights: &[f32], component: &str) -> Result<Vec<f32>> {
+    if let Some(w) = weights.iter().find(|w| !w.is_finite() || **w < 0.0) {
+        anyhow::bail!("Invalid {} blend weight {}", component, w);
+    }
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        anyhow::bail!("{} blend weights sum to zero", component);
+    }
+    Ok(weights.iter().map(|w| w / total).collect())
+}
+
+/// Weighted average of styles with separate (normalized) ttl and dp weights
+pub fn blend_voice_styles(styles: &[Style], ttl_weights: &[f32], dp_weights: &[f32]) -> Result<Style> {
+    if styles.is_empty() {
+        anyhow::bail!("Cannot blend an empty list of voice styles");
+    }
+    if ttl_weights.len() != styles.len() || dp_weights.len() != styles.len() {
+        anyhow::bail!("Expected one ttl and dp weight per voice style");
+    }
+    let first = &styles[0];
+    if styles[1..].iter().any(|s| s.ttl.dim() != first.ttl.dim() || s.dp.dim() != first.dp.dim()) {
         anyhow::bail!("Voice style dimensions mismatch");
     }
 
-    let ttl = &s1.ttl * (1.0 - alpha) + &s2.ttl * alpha;
-    let dp = &s1.dp * (1.0 - alpha) + &s2.dp * alpha;
+    let ttl_weights = normalize_blend_weights(ttl_weights, "ttl")?;
+    let dp_weights = normalize_blend_weights(dp_weights, "dp")?;
+
+    let mut ttl = Array3::<f32>::zeros(first.ttl.dim());
+    let mut dp = Array3::<f32>::zeros(first.dp.dim());
+    for ((style, &wt), &wd) in styles.iter().zip(&ttl_weights).zip(&dp_weights) {
+        ttl.scaled_add(wt, &style.ttl);
+        dp.scaled_add(wd, &style.dp);
+    }
 
     Ok(Style { ttl, dp })
 }
 
+/// Load and blend the voice styles of a recipe
+pub fn load_and_blend_voice_styles(components: &[BlendComponent]) -> Result<Style> {
+    let styles = components
+        .iter()
+        .map(|c| load_voice_style(std::slice::from_ref(&c.path), false))
+        .collect::<Result<Vec<_>>>()?;
+    let ttl_weights: Vec<f32> = components.iter().map(BlendComponent::ttl_weight).collect();
+    let dp_weights: Vec<f32> = components.iter().map(BlendComponent::dp_weight).collect();
+    blend_voice_styles(&styles, &ttl_weights, &dp_weights)
+}
+
+/// Load and mix two voice styles
+pub fn load_and_mix_voice_styles(path1: &str, path2: &str, alpha: f32) -> Result<Style> {
+    let s1 = load_voice_style(&[path1.to_string()], false)?;
+    let s2 = load_voice_style(&[path2.to_string()], false)?;
+    blend_voice_styles(&[s1, s2], &[1.0 - alpha, alpha], &[1.0 - alpha, alpha])
+}
+
 /// Load TTS components
 pub fn load_text_to_speech(onnx_dir: &str, use_gpu: bool) -> Result<TextToSpeech> {
+    load_text_to_speech_with_options(onnx_dir, use_gpu, &EngineOptions::default())
+}
+
+/// Load TTS components with custom ONNX Runtime session options
+pub fn load_text_to_speech_with_options(
+    onnx_dir: &str,
+    use_gpu: bool,
+    options: &EngineOptions,
+) -> Result<TextToSpeech> {
     if use_gpu {
         anyhow::bail!("GPU mode is not supported yet");
     }
-    println!("Using CPU for inference\n");
+    eprintln!("Using CPU for inference\n");
 
     let cfgs = load_cfgs(onnx_dir)?;
 
-    let dp_path = format!("{}/duration_predictor.onnx", onnx_dir);
-    let text_enc_path = format!("{}/text_encoder.onnx", onnx_dir);
-    let vector_est_path = format!("{}/vector_estimator.onnx", onnx_dir);
-    let vocoder_path = format!("{}/vocoder.onnx", onnx_dir);
+    let load = |model: OnnxModel| {
+        let path = format!("{}/{}", onnx_dir, model.file_name());
+        options.for_model(model).build_session(&path)
+    };
 
-    let dp_ort = Session::builder()? 
-        .commit_from_file(&dp_path)?;
-    let text_enc_ort = Session::builder()? 
-        .commit_from_file(&text_enc_path)?;
-    let vector_est_ort = Session::builder()? 
-        .commit_from_file(&vector_est_path)?;
-    let vocoder_ort = Session::builder()? 
-        .commit_from_file(&vocoder_path)?;
+    let dp_ort = load(OnnxModel::DurationPredictor)?;
+    let text_enc_ort = load(OnnxModel::TextEncoder)?;
+    let vector_est_ort = load(OnnxModel::VectorEstimator)?;
+    let vocoder_ort = load(OnnxModel::Vocoder)?;
 
     let unicode_indexer_path = format!("{}/unicode_indexer.json", onnx_dir);
     let text_processor = UnicodeProcessor::new(&unicode_indexer_path)?;
 
-    Ok(TextToSpeech::new(
+    let mut tts = TextToSpeech::new(
         cfgs,
         text_processor,
         dp_ort,
         text_enc_ort,
         vector_est_ort,
         vocoder_ort,
-    ))
-}
\ No newline at end of file
+    );
+    tts.onnx_dir = Some(onnx_dir.to_string());
+    tts.engine_options = options.clone();
+
+    Ok(tts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::s;
+
+    fn test_style() -> Style {
+        Style {
+            ttl: Array3::from_shape_fn((1, 2, 3), |(_, i, j)| i as f32 - j as f32 * 0.25),
+            dp: Array3::from_shape_fn((1, 2, 2), |(_, i, j)| i as f32 * 0.5 + j as f32),
+        }
+    }
+
+    #[test]
+    fn style_binary_round_trip() {
+        let style = test_style();
+        for dtype in [StyleDtype::F32, StyleDtype::F16] {
+            let bytes = encode_style_binary(&style, dtype).unwrap();
+            let (ttl, dp) = decode_style_binary(&bytes).unwrap();
+            // All test values are exact in f16
+            assert_eq!(ttl, style.ttl);
+            assert_eq!(dp, style.dp);
+        }
+    }
+
+    #[test]
+    fn style_binary_rejects_truncated_header() {
+        let bytes = encode_style_binary(&test_style(), StyleDtype::F32).unwrap();
+        assert!(decode_style_binary(&bytes[..STYLE_BINARY_HEADER_LEN]).is_err());
+        assert!(decode_style_binary(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn style_binary_rejects_checksum_mismatch() {
+        let mut bytes = encode_style_binary(&test_style(), StyleDtype::F32).unwrap();
+        bytes[STYLE_BINARY_HEADER_LEN] ^= 0x01;
+        let err = decode_style_binary(&bytes).unwrap_err();
+        assert!(err.to_string().contains("Checksum"), "{}", err);
+    }
+
+    #[test]
+    fn style_binary_rejects_overflowing_dims() {
+        let mut body = encode_style_binary(&test_style(), StyleDtype::F32).unwrap();
+        body.truncate(body.len() - 4);
+        for offset in [8, 12, 16] {
+            body[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        }
+        let checksum = crc32fast::hash(&body);
+        body.extend_from_slice(&checksum.to_le_bytes());
+        assert!(decode_style_binary(&body).is_err());
+    }
+
+    fn row(mask: &Array3<f32>, b: usize) -> Vec<f32> {
+        mask.slice(s![b, 0, ..]).to_vec()
+    }
+
+    #[test]
+    fn masks_cover_each_length_and_pad_with_zeros() {
+        let mask = length_to_mask(&[3, 1, 0], None);
+        assert_eq!(mask.dim(), (3, 1, 3));
+        assert_eq!(row(&mask, 0), [1.0, 1.0, 1.0]);
+        assert_eq!(row(&mask, 1), [1.0, 0.0, 0.0]);
+        assert_eq!(row(&mask, 2), [0.0, 0.0, 0.0]);
+
+        // An explicit length pads further or cuts rows short
+        assert_eq!(row(&length_to_mask(&[2], Some(4)), 0), [1.0, 1.0, 0.0, 0.0]);
+        assert_eq!(row(&length_to_mask(&[5], Some(2)), 0), [1.0, 1.0]);
+        assert_eq!(get_text_mask(&[2, 4]).dim(), (2, 1, 4));
+    }
+
+    #[test]
+    fn encode_pads_rows_and_maps_unknown_characters_to_zero() {
+        let mut indexer = vec![-1i64; 128];
+        indexer['a' as usize] = 5;
+        indexer['b' as usize] = 6;
+        let processor = UnicodeProcessor { indexer };
+
+        let (ids, mask) = processor.encode(&["ab".to_string(), "a?é".to_string(), String::new()]);
+        assert_eq!(ids, [vec![5, 6, 0], vec![5, 0, 0], vec![0, 0, 0]]);
+        assert_eq!(row(&mask, 0), [1.0, 1.0, 0.0]);
+        assert_eq!(row(&mask, 1), [1.0, 1.0, 1.0]);
+        assert_eq!(row(&mask, 2), [0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn seeded_noise_does_not_depend_on_the_batch() {
+        // 20 samples per latent frame: 0.5 s is 25 frames, 0.25 s is 13
+        let sample = |duration: &[f32], seed| sample_noisy_latent(duration, 1000, 10, 2, 1, 1.0, Some(seed)).unwrap();
+
+        let (batch, mask) = sample(&[0.5, 0.25], 7);
+        assert_eq!(batch.dim(), (2, 2, 25));
+        assert_eq!(mask.slice(s![1, 0, ..]).iter().filter(|&&m| m == 1.0).count(), 13);
+
+        // Row b is seeded with seed + b, so it matches the same text alone
+        let (alone, _) = sample(&[0.25], 8);
+        assert_eq!(alone.dim(), (1, 2, 13));
+        assert_eq!(batch.slice(s![1, .., ..13]), alone.slice(s![0, .., ..]));
+        // Padding is masked to zero
+        assert!(batch.slice(s![1, .., 13..]).iter().all(|&v| v == 0.0));
+
+        // Padding a row to a longer batch only appends to its noise
+        let (padded, _) = sample(&[0.25, 0.5], 7);
+        let (short, _) = sample(&[0.25], 7);
+        assert_eq!(padded.slice(s![0, .., ..13]), short.slice(s![0, .., ..]));
+
+        // Without a seed every call draws fresh noise
+        let (first, _) = sample_noisy_latent(&[0.25], 1000, 10, 2, 1, 1.0, None).unwrap();
+        let (second, _) = sample_noisy_latent(&[0.25], 1000, 10, 2, 1, 1.0, None).unwrap();
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,201 @@
+// ============================================================================
+// Pronunciation Lexicon - user-defined replacements applied before synthesis
+// ============================================================================
+//
+// Reads the same JSON array as the Android LexiconManager (user_lexicon.json),
+// with two optional extra fields: `wholeWord` and `langs`.
+
+use anyhow::{Context, Result};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexiconEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub term: String,
+    pub replacement: String,
+    #[serde(default = "default_true")]
+    pub ignore_case: bool,
+    /// `term` is a regular expression and `replacement` may use `$1`-style groups
+    #[serde(default)]
+    pub is_regex: bool,
+    /// Only match `term` as a whole word (ignored for regex entries)
+    #[serde(default = "default_true")]
+    pub whole_word: bool,
+    /// Languages the entry applies to; empty means all languages
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub langs: Vec<String>,
+}
+
+struct CompiledEntry {
+    entry: LexiconEntry,
+    pattern: Regex,
+}
+
+/// Ordered list of replacements; entries are applied one after the other
+#[derive(Default)]
+pub struct Lexicon {
+    entries: Vec<CompiledEntry>,
+    /// Entries dropped because their term does not compile
+    skipped: usize,
+}
+
+impl Lexicon {
+    /// Like LexiconManager, an entry whose term does not compile (e.g. Java-only
+    /// lookbehind) is skipped with a warning and the rest are kept
+    pub fn new(entries: Vec<LexiconEntry>) -> Result<Self> {
+        let mut compiled = Vec::with_capacity(entries.len());
+        let mut skipped = 0;
+        for entry in entries {
+            if entry.term.trim().is_empty() {
+                continue;
+            }
+            match compile(&entry) {
+                Ok(pattern) => compiled.push(CompiledEntry { entry, pattern }),
+                Err(e) => {
+                    log::warn!("Skipping invalid lexicon term {:?}: {:#}", entry.term, e);
+                    skipped += 1;
+                }
+            }
+        }
+        Ok(Lexicon { entries: compiled, skipped })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let entries: Vec<LexiconEntry> = serde_json::from_str(json).context("Invalid lexicon JSON")?;
+        Self::new(entries)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read lexicon: {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LexiconEntry> {
+        self.entries.iter().map(|c| &c.entry)
+    }
+
+    /// Apply every entry scoped to `lang` to `text`
+    pub fn apply(&self, text: &str, lang: &str) -> String {
+        let mut text = text.to_string();
+        if self.is_empty() {
+            return text;
+        }
+        for CompiledEntry { entry, pattern } in &self.entries {
+            if !entry.langs.is_empty() && !entry.langs.iter().any(|l| l == lang) {
+                continue;
+            }
+            text = if entry.is_regex {
+                pattern.replace_all(&text, entry.replacement.as_str()).into_owned()
+            } else {
+                pattern.replace_all(&text, NoExpand(&entry.replacement)).into_owned()
+            };
+        }
+        text
+    }
+}
+
+fn compile(entry: &LexiconEntry) -> Result<Regex> {
+    let source = if entry.is_regex {
+        entry.term.clone()
+    } else {
+        let mut source = regex::escape(&entry.term);
+        // Word boundaries only make sense next to word characters ("C++" has none at the end)
+        if entry.whole_word {
+            let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+            if is_word(entry.term.chars().next()) {
+                source = format!(r"\b{}", source);
+            }
+            if is_word(entry.term.chars().last()) {
+                source = format!(r"{}\b", source);
+            }
+        }
+        source
+    };
+    Ok(RegexBuilder::new(&source).case_insensitive(entry.ignore_case).build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lexicon(json: &str) -> Lexicon {
+        Lexicon::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn plain_terms_match_whole_words_unless_told_otherwise() {
+        let lex = lexicon(r#"[{"term": "cat", "replacement": "kitty"}]"#);
+        assert_eq!(lex.apply("cat catalog Cat", "en"), "kitty catalog kitty");
+
+        let lex = lexicon(r#"[{"term": "cat", "replacement": "kitty", "wholeWord": false}]"#);
+        assert_eq!(lex.apply("cat catalog", "en"), "kitty kittyalog");
+
+        // No boundary is required next to a non-word character
+        let lex = lexicon(r#"[{"term": "C++", "replacement": "C plus plus"}]"#);
+        assert_eq!(lex.apply("I like C++.", "en"), "I like C plus plus.");
+
+        // "$1" in a plain replacement is literal
+        let lex = lexicon(r#"[{"term": "cost", "replacement": "$1"}]"#);
+        assert_eq!(lex.apply("cost", "en"), "$1");
+    }
+
+    #[test]
+    fn regex_terms_expand_groups() {
+        let lex = lexicon(r#"[{"term": "(\\d+)x", "replacement": "$1 times", "isRegex": true}]"#);
+        assert_eq!(lex.apply("3x faster", "en"), "3 times faster");
+    }
+
+    #[test]
+    fn ignore_case_defaults_to_on() {
+        let lex = lexicon(r#"[{"term": "NASA", "replacement": "nasa"}]"#);
+        assert_eq!(lex.apply("Nasa NASA", "en"), "nasa nasa");
+
+        let lex = lexicon(r#"[{"term": "NASA", "replacement": "nasa", "ignoreCase": false}]"#);
+        assert_eq!(lex.apply("Nasa NASA", "en"), "Nasa nasa");
+    }
+
+    #[test]
+    fn entries_are_scoped_to_their_langs() {
+        let lex = lexicon(r#"[{"term": "hola", "replacement": "ola", "langs": ["es"]}]"#);
+        assert_eq!(lex.apply("hola", "es"), "ola");
+        assert_eq!(lex.apply("hola", "en"), "hola");
+    }
+
+    #[test]
+    fn invalid_entries_are_skipped() {
+        let lex = lexicon(
+            r#"[
+                {"term": "(?<=a)b", "replacement": "x", "isRegex": true},
+                {"term": "(a)\\1", "replacement": "x", "isRegex": true},
+                {"term": " ", "replacement": "x"},
+                {"term": "dog", "replacement": "puppy"}
+            ]"#,
+        );
+        assert_eq!(lex.len(), 1);
+        assert_eq!(lex.skipped(), 2);
+        assert_eq!(lex.apply("ab dog", "en"), "ab puppy");
+    }
+}
@@ -1,15 +1,37 @@
 use jni::JNIEnv;
 use jni::objects::{JClass, JString, JObject, JValue};
-use jni::sys::{jlong, jint, jfloat, jbyteArray};
+use jni::sys::{jlong, jint, jfloat, jbyteArray, jstring, jboolean, JNI_FALSE, JNI_TRUE};
 use android_logger::Config;
 use log::LevelFilter;
+use std::cell::Cell;
 use std::time::Instant;
 
-mod helper;
-mod thermal;
+pub mod encoder;
+mod error;
+pub mod helper;
+pub mod lexicon;
+pub mod normalizer;
+pub mod postprocess;
+pub mod resample;
+pub mod ssml;
+mod style_cache;
+pub mod thermal;
+pub mod timing;
+pub mod voices;
 
-use helper::{load_text_to_speech, load_voice_style, load_and_mix_voice_styles, TextToSpeech};
+use encoder::{encode_audio, to_pcm16_dithered, AudioEncoding, Dither};
+use error::{get_string, guard, throw, EngineError, EngineResult};
+use helper::{
+    load_text_to_speech_with_options, ChunkJoin, EngineOptions, SynthesisOptions, TextToSpeech,
+};
+use lexicon::Lexicon;
+use postprocess::PostProcess;
+use resample::MAX_SAMPLE_RATE;
+use ssml::is_ssml;
+use style_cache::{style_directory, StyleCache, DEFAULT_STYLE_CACHE_CAPACITY};
 use thermal::{UnifiedThermalManager, SocClass};
+use timing::WordTiming;
+use voices::VoiceRegistry;
 
 use std::panic;
 
@@ -17,6 +39,22 @@ struct SupertonicEngine {
     tts: TextToSpeech,
     thermal: UnifiedThermalManager,
     last_rtf: f32,
+    last_word_timings: Vec<WordTiming>,
+    /// Output of the last successful `synthesize`, kept for `encodeLastAudio`
+    last_audio: Vec<f32>,
+    last_sample_rate: u32,
+    styles: StyleCache,
+    /// Per-chunk trimming, loudness normalization and smoothed joins
+    /// (`setPostProcessing`); off keeps the plain 0.1 s pause between chunks
+    postprocess: bool,
+}
+
+/// Borrow the engine behind a pointer handed out by `init`
+fn engine_mut<'a>(ptr: jlong) -> EngineResult<&'a mut SupertonicEngine> {
+    if ptr == 0 {
+        return Err(EngineError::NotInitialized);
+    }
+    Ok(unsafe { &mut *(ptr as *mut SupertonicEngine) })
 }
 
 #[no_mangle]
@@ -35,32 +73,40 @@ pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_init(
         log::error!("RUST PANIC: {}", panic_info);
     }));
 
-    let model_path: String = env.get_string(&model_path).expect("Couldn't get java string!").into();
-    
-    log::info!("Initializing Supertonic Engine with model path: {}", model_path);
+    guard(&mut env, 0, |env| {
+        let model_path = get_string(env, &model_path, "modelPath")?;
 
-    if let Err(e) = ort::init().commit() {
-        log::error!("Failed to initialize ORT environment: {:?}", e);
-        return 0;
-    }
+        log::info!("Initializing Supertonic Engine with model path: {}", model_path);
 
-    let tts = match load_text_to_speech(&model_path, false) {
-        Ok(t) => t,
-        Err(e) => {
-            log::error!("Failed to load TTS: {:?}", e);
-            return 0;
-        }
-    };
+        ort::init()
+            .commit()
+            .map_err(|e| EngineError::ModelLoad(anyhow::anyhow!("Failed to initialize ORT environment: {}", e)))?;
+
+        let thermal = UnifiedThermalManager::new();
 
-    let thermal = UnifiedThermalManager::new();
+        // Start with the thread count of the initial thermal mode so the first
+        // synthesis does not have to rebuild the sessions
+        let mut options = EngineOptions::default();
+        options.session.intra_threads = Some(thermal.get_thread_count());
 
-    let engine = SupertonicEngine {
-        tts,
-        thermal,
-        last_rtf: 1.0,
-    };
+        let mut tts = load_text_to_speech_with_options(&model_path, false, &options)
+            .map_err(EngineError::ModelLoad)?;
+        tts.set_thread_budget(thermal.thread_budget());
 
-    Box::into_raw(Box::new(engine)) as jlong
+        let last_sample_rate = tts.sample_rate as u32;
+        let engine = SupertonicEngine {
+            tts,
+            thermal,
+            last_rtf: 1.0,
+            last_word_timings: Vec::new(),
+            last_audio: Vec::new(),
+            last_sample_rate,
+            styles: StyleCache::new(DEFAULT_STYLE_CACHE_CAPACITY),
+            postprocess: false,
+        };
+
+        Ok(Box::into_raw(Box::new(engine)) as jlong)
+    })
 }
 
 #[no_mangle]
@@ -74,162 +120,336 @@ pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_synthesiz
     speed: jfloat,
     buffer_seconds: jfloat,
     steps: jint,
+    sample_rate: jint,
 ) -> jbyteArray {
-    let engine = unsafe { &mut *(ptr as *mut SupertonicEngine) };
-    
-    let text: String = env.get_string(&text).expect("Couldn't get java string!").into();
-    let lang: String = env.get_string(&lang).expect("Couldn't get java string!").into();
-    let style_path: String = env.get_string(&style_path).expect("Couldn't get java string!").into();
-
-    engine.thermal.update(buffer_seconds, engine.last_rtf);
-
-    let style = if style_path.contains(';') {
-        let parts: Vec<&str> = style_path.split(';').collect();
-        if parts.len() == 3 {
-            let p1 = parts[0];
-            let p2 = parts[1];
-            let alpha = parts[2].parse::<f32>().unwrap_or(0.5);
-            match load_and_mix_voice_styles(p1, p2, alpha) {
-                Ok(s) => s,
-                Err(e) => {
-                    log::error!("Failed to mix voice styles: {:?}", e);
-                    return env.new_byte_array(0).unwrap().into_raw();
-                }
-            }
-        } else {
-            log::error!("Invalid mix format. Expected: path1;path2;alpha");
-            return env.new_byte_array(0).unwrap().into_raw();
+    guard(&mut env, std::ptr::null_mut(), |env| {
+        let engine = engine_mut(ptr)?;
+
+        let text = get_string(env, &text, "text")?;
+        let lang = get_string(env, &lang, "lang")?;
+        let style_path = get_string(env, &style_path, "stylePath")?;
+        if steps <= 0 {
+            return Err(EngineError::InvalidArgument(format!("steps must be positive, got {}", steps)));
         }
-    } else {
-        match load_voice_style(&[style_path], false) {
-            Ok(s) => s,
-            Err(e) => {
-                log::error!("Failed to load voice style: {:?}", e);
-                return env.new_byte_array(0).unwrap().into_raw();
+        // 0 keeps the model's rate
+        let output_sample_rate = match sample_rate {
+            0 => None,
+            rate if rate > 0 && rate as u32 <= MAX_SAMPLE_RATE => Some(rate as u32),
+            rate => {
+                return Err(EngineError::InvalidArgument(format!("unsupported sample rate: {}", rate)));
             }
-        }
-    };
-
-    let start = Instant::now();
-    
-    // Create a progress callback
-    let mut last_progress_call = Instant::now();
-    let result = engine.tts.call(&text, &lang, &style, steps as usize, speed, 0.1, |curr, total, audio_chunk| {
-        // Check for cancellation
-        let is_cancelled = env.call_method(&instance, "isCancelled", "()Z", &[]).unwrap().z().unwrap();
-        if is_cancelled {
-            return false;
-        }
+        };
+
+        engine.thermal.update(buffer_seconds, engine.last_rtf);
+
+        let style = engine.styles.get(&style_path)?;
+        engine.tts.validate_style(&style).map_err(EngineError::VoiceStyle)?;
 
-        // Send audio chunk if available
-        if let Some(audio) = audio_chunk {
-            let mut pcm_data = Vec::with_capacity(audio.len() * 2);
-            for &sample in audio {
-                let clamped = sample.max(-1.0).min(1.0);
-                let val = (clamped * 32767.0) as i16;
-                pcm_data.extend_from_slice(&val.to_le_bytes());
+        // Start the per-model breakdown from this request
+        engine.tts.take_stage_timings();
+        let start = Instant::now();
+
+        // Create a progress callback
+        let mut last_progress_call = Instant::now();
+        let cancelled = Cell::new(false);
+        let mut dither = Dither::new();
+        let (join, chunk_postprocess) = if engine.postprocess {
+            // Trim dead air and even out levels between sentences
+            (ChunkJoin::with_sentence_pause(0.1), PostProcess::standard())
+        } else {
+            (ChunkJoin::uniform(0.1), PostProcess::default())
+        };
+        let options = SynthesisOptions {
+            total_step: steps as usize,
+            speed,
+            join,
+            chunk_postprocess,
+            output_sample_rate,
+            ..Default::default()
+        };
+        let progress = |curr: usize, total: usize, audio_chunk: Option<&[f32]>| -> bool {
+            // A failing Java callback stops synthesis just like a cancellation;
+            // its exception stays pending and is what the caller sees
+            let is_cancelled = env
+                .call_method(&instance, "isCancelled", "()Z", &[])
+                .and_then(|v| v.z())
+                .unwrap_or(true);
+            if is_cancelled {
+                cancelled.set(true);
+                return false;
             }
-            
-            let output = env.new_byte_array(pcm_data.len() as i32).unwrap();
-            env.set_byte_array_region(&output, 0, pcm_data.iter().map(|&b| b as i8).collect::<Vec<_>>().as_slice()).unwrap();
-            
-            let _ = env.call_method(
-                &instance,
-                "notifyAudioChunk",
-                "([B)V",
-                &[JValue::Object(&output)],
-            );
-        }
 
-        // Only call Progress JNI every 100ms or at start/end/chunk
-        if curr == 0 || curr == total || audio_chunk.is_some() || last_progress_call.elapsed().as_millis() > 100 {
-            let _ = env.call_method(
-                &instance,
-                "notifyProgress",
-                "(II)V",
-                &[JValue::Int(curr as i32), JValue::Int(total as i32)],
-            );
-            last_progress_call = Instant::now();
-        }
-        true
-    });
-
-    match result {
-        Ok((wav_data, duration)) => {
-            let elapsed = start.elapsed().as_secs_f32();
-            if duration > 0.0 {
-                engine.last_rtf = duration / elapsed;
-                log::info!("Inference RTF: {:.2}x ({}s audio in {}s)", engine.last_rtf, duration, elapsed);
+            // Send audio chunk if available
+            if let Some(audio) = audio_chunk {
+                let sent = env.byte_array_from_slice(&dither.pcm16(audio)).and_then(|output| {
+                    env.call_method(&instance, "notifyAudioChunk", "([B)V", &[JValue::Object(&output)])
+                });
+                if sent.is_err() {
+                    cancelled.set(true);
+                    return false;
+                }
             }
 
-            let mut pcm_data = Vec::with_capacity(wav_data.len() * 2);
-            for &sample in &wav_data {
-                let clamped = sample.max(-1.0).min(1.0);
-                let val = (clamped * 32767.0) as i16;
-                pcm_data.extend_from_slice(&val.to_le_bytes());
+            // Only call Progress JNI every 100ms or at start/end/chunk
+            if curr == 0 || curr == total || audio_chunk.is_some() || last_progress_call.elapsed().as_millis() > 100 {
+                let notified = env.call_method(
+                    &instance,
+                    "notifyProgress",
+                    "(II)V",
+                    &[JValue::Int(curr as i32), JValue::Int(total as i32)],
+                );
+                if notified.is_err() {
+                    cancelled.set(true);
+                    return false;
+                }
+                last_progress_call = Instant::now();
             }
+            true
+        };
 
-            let output = env.new_byte_array(pcm_data.len() as i32).unwrap();
-            env.set_byte_array_region(&output, 0, pcm_data.iter().map(|&b| b as i8).collect::<Vec<_>>().as_slice()).unwrap();
-            output.into_raw()
-        }
-        Err(e) => {
-            log::error!("Synthesis failed: {:?}", e);
-            env.new_byte_array(0).unwrap().into_raw()
+        let result = if is_ssml(&text) {
+            // <voice name="F1"> refers to voice F1 in the selected style's directory
+            let voice_dir = style_directory(&style_path);
+            let registry = VoiceRegistry::scan(&voice_dir).unwrap_or_default();
+            let styles = &mut engine.styles;
+            let resolve_voice = |name: &str| {
+                let path = match registry.get(name) {
+                    Some(voice) => voice.path.clone(),
+                    None => voice_dir.join(if name.ends_with(".json") { name.to_string() } else { format!("{}.json", name) }),
+                };
+                styles
+                    .get(&path.to_string_lossy())
+                    .map(|style| (*style).clone())
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            };
+            engine.tts.call_ssml(&text, &lang, &style, &options, resolve_voice, progress)
+                .map(|(wav, duration)| (wav, duration, Vec::new()))
+        } else {
+            engine.tts.call_with_timing(&text, &lang, &style, &options, progress)
+        };
+
+        let (wav_data, duration, chunk_timings) = result.map_err(|e| {
+            if cancelled.get() { EngineError::Cancelled } else { EngineError::Synthesis(e) }
+        })?;
+
+        engine.last_word_timings = chunk_timings.into_iter().flat_map(|c| c.words).collect();
+        let elapsed = start.elapsed().as_secs_f32();
+        if duration > 0.0 {
+            engine.last_rtf = duration / elapsed;
+            log::info!("Inference RTF: {:.2}x ({}s audio in {}s)", engine.last_rtf, duration, elapsed);
         }
-    }
+        let stages = engine.tts.take_stage_timings();
+        log::debug!(
+            "Model time: dp {:.3}s, text_enc {:.3}s, vector_est {:.3}s ({} steps), vocoder {:.3}s",
+            stages.duration_predictor,
+            stages.text_encoder,
+            stages.vector_estimator_total(),
+            stages.vector_estimator.len(),
+            stages.vocoder
+        );
+
+        let output = env.byte_array_from_slice(&to_pcm16_dithered(&wav_data))?;
+        engine.last_audio = wav_data;
+        engine.last_sample_rate = engine.tts.output_sample_rate(&options) as u32;
+        Ok(output.into_raw())
+    })
+}
+
+/// Encode the audio of the last successful `synthesize` call as a complete
+/// file: `format` is wav, wav24, wav32f, flac, flac24 or opus[:kbps]
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_encodeLastAudio(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    format: JString,
+) -> jbyteArray {
+    guard(&mut env, std::ptr::null_mut(), |env| {
+        let engine = engine_mut(ptr)?;
+        let format = get_string(env, &format, "format")?;
+        let encoding: AudioEncoding = format
+            .parse()
+            .map_err(|e: anyhow::Error| EngineError::InvalidArgument(e.to_string()))?;
+        let bytes = encode_audio(&engine.last_audio, engine.last_sample_rate, encoding)
+            .map_err(EngineError::Synthesis)?;
+        Ok(env.byte_array_from_slice(&bytes)?.into_raw())
+    })
+}
+
+/// Word timings of the last successful `synthesize` call as a JSON array of
+/// `{word, char_start, char_end, start, end}` (times in seconds)
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_getLastWordTimings(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+) -> jstring {
+    guard(&mut env, std::ptr::null_mut(), |env| {
+        let engine = engine_mut(ptr)?;
+        let json = serde_json::to_string(&engine.last_word_timings).unwrap_or_else(|_| "[]".to_string());
+        Ok(env.new_string(json)?.into_raw())
+    })
+}
+
+/// List the voices in `voiceDir` as `{"default": id, "voices": [...]}` JSON.
+/// Does not need an engine.
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_listVoices(
+    mut env: JNIEnv,
+    _class: JClass,
+    voice_dir: JString,
+) -> jstring {
+    guard(&mut env, std::ptr::null_mut(), |env| {
+        let voice_dir = get_string(env, &voice_dir, "voiceDir")?;
+        let json = VoiceRegistry::scan(&voice_dir)
+            .and_then(|registry| registry.to_json())
+            .map_err(EngineError::VoiceStyle)?;
+        Ok(env.new_string(json)?.into_raw())
+    })
+}
+
+/// Replace the engine's pronunciation lexicon with the JSON array `json`
+/// (the format of the app's user_lexicon.json). An empty string clears it.
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_setLexicon(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    json: JString,
+) -> jboolean {
+    guard(&mut env, JNI_FALSE, |env| {
+        let engine = engine_mut(ptr)?;
+        let json = get_string(env, &json, "json")?;
+
+        let lexicon = if json.trim().is_empty() {
+            Lexicon::default()
+        } else {
+            Lexicon::from_json(&json).map_err(|e| EngineError::InvalidArgument(format!("{:#}", e)))?
+        };
+        log::info!("Loaded {} lexicon entries ({} invalid skipped)", lexicon.len(), lexicon.skipped());
+        engine.tts.set_lexicon(lexicon);
+        Ok(JNI_TRUE)
+    })
+}
+
+/// Parse `stylePath` (a file or blend recipe) into the style cache
+/// ahead of the first `synthesize` that uses it, and check it fits the models
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_preloadStyle(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    style_path: JString,
+) -> jboolean {
+    guard(&mut env, JNI_FALSE, |env| {
+        let engine = engine_mut(ptr)?;
+        let style_path = get_string(env, &style_path, "stylePath")?;
+        let style = engine.styles.get(&style_path)?;
+        engine.tts.validate_style(&style).map_err(EngineError::VoiceStyle)?;
+        Ok(JNI_TRUE)
+    })
+}
+
+/// Drop `stylePath` (and blends using it) from the style cache; returns the
+/// number of evicted entries
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_evictStyle(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    style_path: JString,
+) -> jint {
+    guard(&mut env, 0, |env| {
+        let engine = engine_mut(ptr)?;
+        let style_path = get_string(env, &style_path, "stylePath")?;
+        Ok(engine.styles.evict(&style_path)? as jint)
+    })
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_clearStyleCache(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+) {
+    guard(&mut env, (), |_| {
+        let engine = engine_mut(ptr)?;
+        log::info!("Clearing {} cached voice styles", engine.styles.len());
+        engine.styles.clear();
+        Ok(())
+    })
+}
+
+/// Turn per-chunk post-processing (trim, -18 LUFS, -1 dBFS limit) and
+/// boundary-aware joins on or off for the following `synthesize` calls
+#[no_mangle]
+pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_setPostProcessing(
+    mut env: JNIEnv,
+    _class: JClass,
+    ptr: jlong,
+    enabled: jboolean,
+) {
+    guard(&mut env, (), |_| {
+        let engine = engine_mut(ptr)?;
+        engine.postprocess = enabled != JNI_FALSE;
+        log::info!("Chunk post-processing {}", if engine.postprocess { "enabled" } else { "disabled" });
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_getSocClass(
-    _env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
     ptr: jlong,
 ) -> jint {
-    if ptr == 0 { return -1; }
-    let engine = unsafe { &mut *(ptr as *mut SupertonicEngine) };
-    match engine.thermal.get_soc_class() {
-        SocClass::Flagship => 3,
-        SocClass::HighEnd => 2,
-        SocClass::MidRange => 1,
-        SocClass::LowEnd => 0,
-    }
+    guard(&mut env, -1, |_| {
+        let engine = engine_mut(ptr)?;
+        Ok(match engine.thermal.get_soc_class() {
+            SocClass::Flagship => 3,
+            SocClass::HighEnd => 2,
+            SocClass::MidRange => 1,
+            SocClass::LowEnd => 0,
+        })
+    })
 }
 
 #[no_mangle]
 pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_getSampleRate(
-    _env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
     ptr: jlong,
 ) -> jint {
-    if ptr == 0 { return 24000; }
-    let engine = unsafe { &mut *(ptr as *mut SupertonicEngine) };
-    engine.tts.sample_rate as jint
+    guard(&mut env, 24000, |_| {
+        let engine = engine_mut(ptr)?;
+        Ok(engine.tts.sample_rate as jint)
+    })
 }
 
 #[no_mangle]
 pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_reset(
-    _env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
     ptr: jlong,
 ) {
-    if ptr != 0 {
-        let engine = unsafe { &mut *(ptr as *mut SupertonicEngine) };
+    guard(&mut env, (), |_| {
+        let engine = engine_mut(ptr)?;
         // Reset thermal state or other buffers if needed
         engine.last_rtf = 1.0;
         log::info!("Engine state reset (JNI Handshake)");
-    }
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "system" fn Java_com_brahmadeo_supertonic_tts_SupertonicTTS_close(
-    _env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
     ptr: jlong,
 ) {
-    if ptr != 0 {
-        unsafe {
-            let _ = Box::from_raw(ptr as *mut SupertonicEngine);
-        }
+    if ptr == 0 {
+        throw(&mut env, &EngineError::NotInitialized);
+        return;
     }
+    drop(unsafe { Box::from_raw(ptr as *mut SupertonicEngine) });
 }
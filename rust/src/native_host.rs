@@ -0,0 +1,499 @@
+// ============================================================================
+// Chrome native messaging host
+// ============================================================================
+//
+// Speaks Chrome's stdio protocol: every message is a 4-byte native-endian
+// length followed by that many bytes of JSON. Commands are those of
+// `cpp/native_host.cpp` plus voice listing and cancellation:
+//
+//   {"command": "initialize", "onnx_dir", "voice_dir", "use_gpu", "lexicon"}
+//       -> {"status": "initialized", "sample_rate"}
+//   {"command": "synthesize", "text", "lang", "voice" | "voice_style_path",
+//    "speed", "total_step", "seed", "sample_rate", "stream"}
+//       -> {"status": "success", "audio": <base64 16-bit PCM>, "sample_rate", "duration"}
+//   {"command": "list_voices"}  -> {"status": "success", "voices": [...]}
+//   {"command": "cancel"}       -> {"status": "cancelled"} for the running synthesis
+//                                  and for the cancel itself, or {"status": "idle"}
+//   {"command": "ping"}         -> {"status": "pong"}
+//
+// Failures reply {"error": message}. Replies echo the request's `id` if it
+// has one.
+//
+// Chrome drops host messages over 1 MB, so long audio is split: a
+// synthesis sends `{"status": "partial", "audio"}` messages before its final
+// `success`, or with `"stream": true` one or more `{"status": "chunk",
+// "index", "chunks", "audio", ...}` messages per text chunk as soon as it is
+// synthesized. Clients append the `audio` of every `partial`/`chunk` message
+// and of the final `success`. Commands sent during a synthesis are handled
+// when it finishes, except `cancel` and `ping`, which are answered between
+// chunks.
+
+use anyhow::{Context, Result};
+use base64::Engine as _;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use supertonic_tts::encoder::{to_pcm16_dithered, Dither};
+use supertonic_tts::helper::{
+    is_valid_lang, load_text_to_speech, load_voice_style, Style, SynthesisOptions, TextToSpeech,
+};
+use supertonic_tts::lexicon::Lexicon;
+use supertonic_tts::resample::Resampler;
+use supertonic_tts::ssml::{is_ssml, parse_ssml};
+use supertonic_tts::voices::VoiceRegistry;
+
+/// Chrome's limit for a message from the host
+const MAX_MESSAGE_BYTES: usize = 1024 * 1024;
+/// Chrome's limit for a message to the host
+const MAX_REQUEST_BYTES: usize = 64 * 1024 * 1024;
+/// PCM bytes per reply; base64 grows them by a third, leaving room for the
+/// rest of the message
+const MAX_AUDIO_BYTES: usize = 512 * 1024;
+
+/// Searched in order when `initialize` does not name an existing directory
+const ONNX_DIRS: [&str; 3] = ["../../assets/onnx", "../assets/onnx", "assets/onnx"];
+const VOICE_DIRS: [&str; 3] = ["../../assets/voice_styles", "../assets/voice_styles", "assets/voice_styles"];
+
+/// A message from the browser, or why it could not be parsed
+type Incoming = Result<Value, String>;
+
+#[derive(Debug, Deserialize)]
+struct InitializeRequest {
+    #[serde(default)]
+    onnx_dir: Option<String>,
+    #[serde(default)]
+    voice_dir: Option<String>,
+    #[serde(default)]
+    use_gpu: bool,
+    /// Pronunciation lexicon JSON file
+    #[serde(default)]
+    lexicon: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SynthesizeRequest {
+    #[serde(default)]
+    text: String,
+    #[serde(default = "default_lang")]
+    lang: String,
+    /// Voice id from the voice directory
+    #[serde(default)]
+    voice: Option<String>,
+    /// Voice style JSON file
+    #[serde(default)]
+    voice_style_path: Option<String>,
+    #[serde(default)]
+    speed: Option<f32>,
+    #[serde(default)]
+    total_step: Option<usize>,
+    #[serde(default)]
+    seed: Option<u64>,
+    /// Output sample rate (Hz); the model's rate by default
+    #[serde(default)]
+    sample_rate: Option<u32>,
+    /// Send each text chunk as soon as it is synthesized
+    #[serde(default)]
+    stream: bool,
+}
+
+fn default_lang() -> String {
+    "en".to_string()
+}
+
+/// Write one length-prefixed message to stdout
+fn send_message(message: &Value) -> Result<()> {
+    let bytes = serde_json::to_vec(message)?;
+    if bytes.len() > MAX_MESSAGE_BYTES {
+        anyhow::bail!("Reply of {} bytes exceeds the 1 MB native messaging limit", bytes.len());
+    }
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(&(bytes.len() as u32).to_ne_bytes())?;
+    stdout.write_all(&bytes)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Send `message`, tagged with the request's id
+fn reply(id: &Value, mut message: Value) -> Result<()> {
+    if !id.is_null() {
+        message["id"] = id.clone();
+    }
+    send_message(&message)
+}
+
+/// Read one length-prefixed message; `None` once the browser closes stdin
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>> {
+    let mut length = [0u8; 4];
+    match input.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_REQUEST_BYTES {
+        anyhow::bail!("Message of {} bytes exceeds the 64 MB native messaging limit", length);
+    }
+    let mut message = vec![0u8; length];
+    input.read_exact(&mut message)?;
+    Ok(Some(message))
+}
+
+/// Read messages on a separate thread so `cancel` arrives while synthesizing
+fn spawn_reader() -> Receiver<Incoming> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut stdin = io::stdin().lock();
+        loop {
+            let message = match read_message(&mut stdin) {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Failed to read message: {:#}", e);
+                    break;
+                }
+            };
+            let parsed = serde_json::from_slice(&message).map_err(|e| format!("JSON parse error: {}", e));
+            if sender.send(parsed).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn command(message: &Value) -> &str {
+    message.get("command").and_then(Value::as_str).unwrap_or("")
+}
+
+fn message_id(message: &Value) -> Value {
+    message.get("id").cloned().unwrap_or(Value::Null)
+}
+
+/// `requested` if it exists, else the first existing fallback
+fn find_dir(requested: Option<&str>, fallbacks: &[&str]) -> Option<String> {
+    requested
+        .into_iter()
+        .chain(fallbacks.iter().copied())
+        .find(|dir| Path::new(dir).is_dir())
+        .map(str::to_string)
+}
+
+/// Audio going back to the browser in messages under the size limit
+struct AudioReply<'a> {
+    id: &'a Value,
+    sample_rate: i32,
+}
+
+impl AudioReply<'_> {
+    /// Send every piece of `pcm` but the last as `partial` and return the last
+    fn send_partial<'p>(&self, pcm: &'p [u8]) -> Result<&'p [u8]> {
+        let mut pieces = pcm.chunks(MAX_AUDIO_BYTES).peekable();
+        while let Some(piece) = pieces.next() {
+            if pieces.peek().is_none() {
+                return Ok(piece);
+            }
+            reply(
+                self.id,
+                json!({ "status": "partial", "audio": encode(piece), "sample_rate": self.sample_rate }),
+            )?;
+        }
+        Ok(&[])
+    }
+
+    fn send_success(&self, pcm: &[u8], duration: f32) -> Result<()> {
+        let last = self.send_partial(pcm)?;
+        reply(
+            self.id,
+            json!({
+                "status": "success",
+                "audio": encode(last),
+                "sample_rate": self.sample_rate,
+                "duration": duration,
+            }),
+        )
+    }
+
+    /// Send one text chunk as `chunk` messages; its timing goes with the first
+    fn send_chunk(&self, mut message: Value, pcm: &[u8]) -> Result<()> {
+        for (part, piece) in pcm.chunks(MAX_AUDIO_BYTES).enumerate() {
+            if part > 0 {
+                message = json!({ "index": message["index"], "chunks": message["chunks"] });
+            }
+            message["status"] = json!("chunk");
+            message["audio"] = json!(encode(piece));
+            message["sample_rate"] = json!(self.sample_rate);
+            reply(self.id, message.clone())?;
+        }
+        Ok(())
+    }
+}
+
+fn encode(pcm: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(pcm)
+}
+
+struct Host {
+    tts: Option<TextToSpeech>,
+    voices: Option<VoiceRegistry>,
+    /// Loaded styles by voice id or file path
+    styles: HashMap<String, Style>,
+    messages: Receiver<Incoming>,
+    /// Messages that arrived during a synthesis, handled after it
+    pending: VecDeque<Incoming>,
+}
+
+impl Host {
+    fn next_message(&mut self) -> Option<Incoming> {
+        self.pending.pop_front().or_else(|| self.messages.recv().ok())
+    }
+
+    fn handle(&mut self, message: &Value) -> Result<()> {
+        let id = message_id(message);
+        match command(message) {
+            "initialize" => self.initialize(message, &id),
+            "synthesize" => self.synthesize(message, &id),
+            "list_voices" => {
+                let voices = self.voices.as_ref().context("No voice directory loaded")?;
+                reply(&id, json!({ "status": "success", "voices": voices.to_value() }))
+            }
+            // Nothing is running, or the synthesis it was meant for has finished
+            "cancel" => reply(&id, json!({ "status": "idle" })),
+            "ping" => reply(&id, json!({ "status": "pong" })),
+            _ => reply(&id, json!({ "error": "Unknown command" })),
+        }
+    }
+
+    fn initialize(&mut self, message: &Value, id: &Value) -> Result<()> {
+        let req: InitializeRequest = serde_json::from_value(message.clone())?;
+        let onnx_dir = find_dir(req.onnx_dir.as_deref(), &ONNX_DIRS)
+            .context("ONNX model directory not found")?;
+        eprintln!("Initializing TTS with models in: {}", onnx_dir);
+        let mut tts = load_text_to_speech(&onnx_dir, req.use_gpu)?;
+        if let Some(path) = &req.lexicon {
+            let lexicon = Lexicon::load(path)?;
+            eprintln!("Loaded {} lexicon entries from {} ({} invalid skipped)", lexicon.len(), path, lexicon.skipped());
+            tts.set_lexicon(lexicon);
+        }
+
+        // Without a voice directory, styles can still be given by path
+        self.voices = match find_dir(req.voice_dir.as_deref(), &VOICE_DIRS) {
+            Some(dir) => match VoiceRegistry::scan(&dir) {
+                Ok(voices) => {
+                    eprintln!("Loaded {} voices from {}", voices.voices().len(), dir);
+                    Some(voices)
+                }
+                Err(e) => {
+                    eprintln!("Failed to load voices from {}: {:#}", dir, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        self.styles.clear();
+
+        let sample_rate = tts.sample_rate;
+        self.tts = Some(tts);
+        reply(id, json!({ "status": "initialized", "sample_rate": sample_rate }))
+    }
+
+    fn style(&mut self, req: &SynthesizeRequest) -> Result<Style> {
+        let path = match (&req.voice, &req.voice_style_path) {
+            (Some(voice), _) => self.voice_path(voice)?,
+            (None, Some(path)) if !path.is_empty() => path.clone(),
+            _ => self
+                .voices
+                .as_ref()
+                .and_then(|voices| voices.default_voice())
+                .map(|voice| voice.path.to_string_lossy().into_owned())
+                .context("Voice style path is empty")?,
+        };
+        self.load_style(path)
+    }
+
+    fn voice_path(&self, voice: &str) -> Result<String> {
+        let voices = self.voices.as_ref().context("No voice directory loaded")?;
+        let voice = voices
+            .get(voice)
+            .ok_or_else(|| anyhow::anyhow!("Unknown voice '{}'", voice))?;
+        Ok(voice.path.to_string_lossy().into_owned())
+    }
+
+    /// Load and check a style file, or take it from the cache
+    fn load_style(&mut self, path: String) -> Result<Style> {
+        if let Some(style) = self.styles.get(&path) {
+            return Ok(style.clone());
+        }
+        let style = load_voice_style(std::slice::from_ref(&path), false)?;
+        if let Some(tts) = &self.tts {
+            tts.validate_style(&style)?;
+        }
+        self.styles.insert(path, style.clone());
+        Ok(style)
+    }
+
+    fn options(req: &SynthesizeRequest, tts: &TextToSpeech) -> Result<SynthesisOptions> {
+        if req.text.trim().is_empty() {
+            anyhow::bail!("Text is empty");
+        }
+        if !is_valid_lang(&req.lang) {
+            anyhow::bail!("Unsupported language '{}'", req.lang);
+        }
+        let mut options = SynthesisOptions {
+            seed: req.seed,
+            output_sample_rate: req.sample_rate,
+            ..Default::default()
+        };
+        if let Some(speed) = req.speed {
+            if !(speed.is_finite() && speed > 0.0) {
+                anyhow::bail!("Invalid speed {}", speed);
+            }
+            options.speed = speed;
+        }
+        if let Some(steps) = req.total_step {
+            if !(1..=100).contains(&steps) {
+                anyhow::bail!("total_step must be 1..=100, got {}", steps);
+            }
+            options.total_step = steps;
+        }
+        if let Some(rate) = req.sample_rate {
+            Resampler::new(tts.sample_rate as u32, rate)?;
+        }
+        Ok(options)
+    }
+
+    fn synthesize(&mut self, message: &Value, id: &Value) -> Result<()> {
+        let req: SynthesizeRequest = serde_json::from_value(message.clone())?;
+        if self.tts.is_none() {
+            anyhow::bail!("TTS not initialized. Send 'initialize' command first.");
+        }
+        let style = self.style(&req)?;
+        // SSML voices are loaded up front, while `self` is free to borrow
+        let mut ssml_voices = HashMap::new();
+        if is_ssml(&req.text) {
+            for segment in parse_ssml(&req.text, &req.lang)?.segments {
+                if let Some(name) = segment.voice.filter(|name| !ssml_voices.contains_key(name)) {
+                    let style = self.voice_path(&name).and_then(|path| self.load_style(path))?;
+                    ssml_voices.insert(name, style);
+                }
+            }
+        }
+        let Host { tts, messages, pending, .. } = self;
+        let tts = tts.as_mut().unwrap();
+        let options = Self::options(&req, tts)?;
+        let audio = AudioReply { id, sample_rate: tts.output_sample_rate(&options) };
+
+        if is_ssml(&req.text) {
+            // SSML is rendered in one go, checking for a cancel between chunks
+            let resolve_voice = |name: &str| {
+                ssml_voices
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Unknown voice '{}'", name))
+            };
+            let mut cancelled = false;
+            let mut failure = None;
+            let progress = |_, _, _: Option<&[f32]>| match cancel_requested(messages, pending) {
+                Ok(requested) => {
+                    cancelled = requested;
+                    !requested
+                }
+                Err(e) => {
+                    failure = Some(e);
+                    false
+                }
+            };
+            let result = tts.call_ssml(&req.text, &req.lang, &style, &options, resolve_voice, progress);
+            if let Some(e) = failure {
+                return Err(e);
+            }
+            if cancelled {
+                return reply(id, json!({ "status": "cancelled" }));
+            }
+            let (wav, duration) = result?;
+            return audio.send_success(&to_pcm16_dithered(&wav), duration);
+        }
+
+        let mut stream = tts.stream(&req.text, &req.lang, &style, &options);
+        let chunks = stream.num_chunks();
+        let mut samples = Vec::new();
+        let mut duration = 0.0;
+        let mut dither = Dither::new();
+        while !stream.is_finished() {
+            if cancel_requested(messages, pending)? {
+                return reply(id, json!({ "status": "cancelled" }));
+            }
+            let frame = match stream.next() {
+                Some(frame) => frame?,
+                None => break,
+            };
+            duration = frame.end();
+            if req.stream {
+                let timing = json!({
+                    "index": frame.chunk_index,
+                    "chunks": chunks,
+                    "text": frame.text,
+                    "start": frame.start,
+                    "end": frame.end(),
+                    "words": frame.words,
+                });
+                audio.send_chunk(timing, &dither.pcm16(&frame.samples))?;
+            } else {
+                samples.extend_from_slice(&frame.samples);
+            }
+        }
+        audio.send_success(&to_pcm16_dithered(&samples), duration)
+    }
+}
+
+/// Answer pings and look for a cancel among the messages that arrived
+/// during a synthesis; everything else waits in `pending`. The cancel is
+/// acknowledged under its own id before the synthesis replies `cancelled`.
+fn cancel_requested(messages: &Receiver<Incoming>, pending: &mut VecDeque<Incoming>) -> Result<bool> {
+    loop {
+        match messages.try_recv() {
+            Ok(Ok(message)) if command(&message) == "cancel" => {
+                reply(&message_id(&message), json!({ "status": "cancelled" }))?;
+                return Ok(true);
+            }
+            Ok(Ok(message)) if command(&message) == "ping" => {
+                reply(&message_id(&message), json!({ "status": "pong" }))?;
+            }
+            Ok(message) => pending.push_back(message),
+            Err(TryRecvError::Empty) => return Ok(false),
+            // The browser went away; nobody is listening for the rest
+            Err(TryRecvError::Disconnected) => return Ok(true),
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    // Chrome passes the caller's origin (and on Windows a window handle) as
+    // arguments; there is nothing to configure from them
+    eprintln!("Starting Native Messaging Host...");
+
+    let mut host = Host {
+        tts: None,
+        voices: None,
+        styles: HashMap::new(),
+        messages: spawn_reader(),
+        pending: VecDeque::new(),
+    };
+
+    while let Some(message) = host.next_message() {
+        let result = match message {
+            Ok(message) => host.handle(&message).map_err(|e| (message_id(&message), e)),
+            Err(parse_error) => Err((Value::Null, anyhow::anyhow!(parse_error))),
+        };
+        if let Err((id, e)) = result {
+            eprintln!("Error processing request: {:#}", e);
+            reply(&id, json!({ "error": format!("{:#}", e) }))?;
+        }
+    }
+    eprintln!("EOF received, exiting.");
+    Ok(())
+}
@@ -0,0 +1,1653 @@
+// ============================================================================
+// Text Normalizer - spells out numbers, currencies and units
+// ============================================================================
+//
+// Rust port of the Chrome extension's textProcessor.js / numberUtils.js /
+// currencyNormalizer.js (and the Android TextNormalizer), extended with rules
+// for every language in AVAILABLE_LANGS. English output matches the extension
+// ("$4.5bn" -> "four point five billion dollars", "2024" -> "twenty twenty four").
+
+use regex::{Captures, Match, Regex};
+use std::sync::LazyLock;
+
+// ============================================================================
+// Language Rules
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Currency {
+    Dollar,
+    UsDollar,
+    CanadianDollar,
+    AustralianDollar,
+    NewZealandDollar,
+    HongKongDollar,
+    SingaporeDollar,
+    Real,
+    Pound,
+    BritishPound,
+    Euro,
+    Rupee,
+    IndianRupee,
+    Yen,
+    JapaneseYen,
+    Yuan,
+    Won,
+    KoreanWon,
+    Riyal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Kilometer,
+    Meter,
+    Centimeter,
+    Millimeter,
+    Kilogram,
+    Gram,
+    Milligram,
+    PoundWeight,
+    Mile,
+    Hour,
+    KilometerPerHour,
+    MilePerHour,
+    MeterPerSecond,
+    Celsius,
+    Fahrenheit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Magnitude {
+    Thousand,
+    Million,
+    Billion,
+    Trillion,
+    Lakh,
+    Crore,
+}
+
+/// Words and spelling functions for one language
+struct LangRules {
+    /// Decimal separator is "," and "." groups thousands
+    decimal_comma: bool,
+    cardinal: fn(u64) -> String,
+    /// (number, feminine)
+    ordinal: fn(u64, bool) -> String,
+    point: &'static str,
+    minus: &'static str,
+    plus: &'static str,
+    percent: &'static str,
+    range: &'static str,
+    /// Joins major and minor currency units ("ten dollars and fifty cents")
+    and: &'static str,
+    /// Placed between a million-or-larger magnitude and a noun ("millones de dólares")
+    of: &'static str,
+    magnitude: fn(Magnitude, bool) -> &'static str,
+    currency: fn(Currency, bool) -> &'static str,
+    minor_unit: fn(Currency, bool) -> Option<&'static str>,
+    /// Template with "{}" for the spoken amount
+    unit: fn(Unit, bool) -> &'static str,
+    titles: &'static [(&'static str, &'static str)],
+}
+
+fn rules_for(lang: &str) -> Option<&'static LangRules> {
+    match lang {
+        "en" => Some(&EN),
+        "es" => Some(&ES),
+        "pt" => Some(&PT),
+        "fr" => Some(&FR),
+        "ko" => Some(&KO),
+        _ => None,
+    }
+}
+
+/// Expand numbers, currencies, units, percentages, ordinals, years and
+/// phone-style digit sequences into words for `lang`. Unknown languages are
+/// returned unchanged.
+pub fn normalize_text(text: &str, lang: &str) -> String {
+    let rules = match rules_for(lang) {
+        Some(rules) => rules,
+        None => return text.to_string(),
+    };
+    let n = Normalizer { rules, lang };
+
+    let mut text = n.strip_digit_grouping(text);
+    text = n.expand_phone_numbers(&text);
+    if lang == "en" {
+        text = n.expand_emergency_numbers(&text);
+    }
+    // Before the passes that take a leading sign, so "10-20°C" is a range
+    text = n.expand_ranges(&text);
+    text = n.expand_currencies(&text);
+    text = n.expand_units(&text);
+    text = n.expand_magnitudes(&text);
+    text = n.expand_percentages(&text);
+    text = n.expand_ordinals(&text);
+    if lang == "en" {
+        text = n.expand_years(&text);
+    }
+    text = n.expand_titles(&text);
+    text = n.expand_negatives(&text);
+    n.expand_numbers(&text)
+}
+
+// ============================================================================
+// Rule Application
+// ============================================================================
+
+/// Amount after digit grouping was removed
+const AMOUNT_POINT: &str = r"\d+(?:\.\d+)?";
+const AMOUNT_COMMA: &str = r"\d+(?:[.,]\d+)?";
+
+/// A pattern around an amount, compiled once for each decimal separator
+struct AmountRegex {
+    point: LazyLock<Regex>,
+    comma: LazyLock<Regex>,
+}
+
+impl AmountRegex {
+    fn get(&self, decimal_comma: bool) -> &Regex {
+        if decimal_comma { &self.comma } else { &self.point }
+    }
+}
+
+/// `AmountRegex` for a format string with an `{amount}` placeholder
+macro_rules! amount_regex {
+    ($pattern:literal) => {
+        AmountRegex {
+            point: LazyLock::new(|| Regex::new(&format!($pattern, amount = AMOUNT_POINT)).unwrap()),
+            comma: LazyLock::new(|| Regex::new(&format!($pattern, amount = AMOUNT_COMMA)).unwrap()),
+        }
+    };
+}
+
+/// Words that can follow a number without being what it counts
+/// ("21 y 22", "1 de mayo", "3 ou 4")
+const FUNCTION_WORDS: &[&str] = &[
+    "a", "à", "al", "ao", "com", "con", "da", "das", "de", "del", "des", "do", "dos", "du", "e",
+    "em", "en", "entre", "es", "est", "et", "ni", "o", "ou", "para", "por", "pour", "que", "u",
+    "y",
+];
+
+fn is_noun(word: &str) -> bool {
+    word.starts_with(char::is_lowercase) && !FUNCTION_WORDS.contains(&word)
+}
+
+/// English words that can follow a year ("in 2024 the", "1999 was")
+const EN_FUNCTION_WORDS: &[&str] = &[
+    "a", "after", "also", "an", "and", "are", "as", "at", "before", "but", "by", "for", "from",
+    "had", "has", "have", "he", "i", "in", "is", "it", "of", "on", "or", "she", "since", "so",
+    "that", "the", "then", "there", "they", "this", "through", "to", "until", "was", "we", "were",
+    "when", "will", "with", "you",
+];
+
+/// Unit symbols that are not lowercase words ("2048 MB")
+const UNIT_SYMBOLS: &[&str] = &["B", "KB", "kB", "MB", "GB", "TB", "Hz", "kHz", "MHz", "GHz"];
+
+/// Whether `word` after an English number makes it a count ("2048 bytes")
+/// rather than a year ("in 2024 the")
+fn en_counts(word: &str) -> bool {
+    UNIT_SYMBOLS.contains(&word) || (word.starts_with(char::is_lowercase) && !EN_FUNCTION_WORDS.contains(&word))
+}
+
+/// `Regex::replace_all` where the closure also sees the char right after the
+/// match (emulating a lookahead) and can return `None` to keep the match.
+fn replace_checked<F>(re: &Regex, text: &str, mut f: F) -> String
+where
+    F: FnMut(&Captures, Option<char>) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for caps in re.captures_iter(text) {
+        let m = caps.get(0).unwrap();
+        let next = text[m.end()..].chars().next();
+        if let Some(replacement) = f(&caps, next) {
+            out.push_str(&text[last..m.start()]);
+            out.push_str(&replacement);
+            last = m.end();
+        }
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric())
+}
+
+fn char_before(text: &str, pos: usize) -> Option<char> {
+    text[..pos].chars().next_back()
+}
+
+/// Whether a decimal or grouped part follows `pos` ("112" in "112.5")
+fn continues_number(text: &str, pos: usize) -> bool {
+    let mut after = text[pos..].chars();
+    matches!(after.next(), Some('.' | ',')) && after.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Whether `m` is one part of a dotted version string such as "2.0.1"
+fn in_version(text: &str, m: Match) -> bool {
+    let mut after = text[m.end()..].chars();
+    let dotted_after = after.next() == Some('.') && after.next().is_some_and(|c| c.is_ascii_digit());
+    let before = &text[..m.start()];
+    let dotted_before = before
+        .strip_suffix('.')
+        .is_some_and(|rest| rest.ends_with(|c: char| c.is_ascii_digit()));
+    dotted_after || dotted_before
+}
+
+struct Normalizer<'a> {
+    rules: &'static LangRules,
+    lang: &'a str,
+}
+
+impl Normalizer<'_> {
+    fn strip_digit_grouping(&self, text: &str) -> String {
+        // 1.234.567 / 1 234 567 (incl. no-break spaces)
+        static COMMA: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"(?-u:\b)\d{1,3}(?:[. \u{A0}\u{202F}]\d{3})+(?-u:\b)").unwrap());
+        static POINT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?-u:\b)\d{1,3}(?:,\d{3})+(?-u:\b)").unwrap());
+        let re = if self.rules.decimal_comma { &COMMA } else { &POINT };
+        // "1.234,5" keeps its decimal part; "1,234.5" likewise
+        replace_checked(re, text, |caps, _| Some(caps[0].chars().filter(|c| c.is_ascii_digit()).collect()))
+    }
+
+    fn digits(&self, digits: &str) -> String {
+        digits
+            .chars()
+            .filter_map(|c| c.to_digit(10))
+            .map(|d| (self.rules.cardinal)(d as u64))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn cardinal_str(&self, digits: &str) -> String {
+        // Leading zeros ("007") and huge numbers are read digit by digit
+        if digits.len() > 1 && digits.starts_with('0') {
+            return self.digits(digits);
+        }
+        match digits.parse::<u64>() {
+            Ok(n) if n < 1_000_000_000_000_000 => (self.rules.cardinal)(n),
+            _ => self.digits(digits),
+        }
+    }
+
+    /// Spell an amount such as "4.5" / "4,5" (integer part as a cardinal,
+    /// fraction digit by digit)
+    fn spell_amount(&self, amount: &str) -> String {
+        match amount.find(['.', ',']) {
+            Some(pos) => format!(
+                "{} {} {}",
+                self.cardinal_str(&amount[..pos]),
+                self.rules.point,
+                self.digits(&amount[pos + 1..])
+            ),
+            None => self.cardinal_str(amount),
+        }
+    }
+
+    fn is_one(amount: &str) -> bool {
+        amount == "1"
+    }
+
+    /// "minus" for a sign that starts a word ("-5°C"); a sign joined to the
+    /// word before it ("x-5°C") stays a hyphen. Ranges ("10-20°C") are read
+    /// before any pass that calls this.
+    fn sign(&self, text: &str, sign: Option<Match>) -> String {
+        match sign {
+            Some(m) if char_before(text, m.start()).is_none_or(|c| c.is_whitespace() || c == '(') => {
+                format!("{} ", self.rules.minus)
+            }
+            Some(m) => m.as_str().to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Join a million-or-larger amount and the noun it counts with `of`
+    /// ("um milhão de pessoas", "un million d'habitants")
+    fn join_of(&self, spoken: &str, space: &str, noun: &str) -> String {
+        match self.rules.of.trim() {
+            "" => format!("{}{}{}", spoken, space, noun),
+            "de" if self.lang == "fr" && noun.starts_with(['a', 'e', 'i', 'o', 'u', 'é', 'h']) => {
+                format!("{} d'{}", spoken, noun)
+            }
+            of => format!("{} {}{}{}", spoken, of, space, noun),
+        }
+    }
+
+    // ------------------------------------------------------------------------
+
+    fn expand_phone_numbers(&self, text: &str) -> String {
+        static RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(
+                r"(\+\d{1,3}[ .-]?)?(?-u:\b)(?:\(\d{2,4}\)[ .-]?|\d{2,4}[ .-])\d{3,4}[ .-]\d{3,4}(?-u:\b)|(?-u:\b)\d{3}-\d{4}(?-u:\b)",
+            )
+            .unwrap()
+        });
+        static GROUP: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").unwrap());
+        replace_checked(&RE, text, |caps, next| {
+            if is_word_char(next) {
+                return None;
+            }
+            let whole = &caps[0];
+            let mut parts: Vec<String> = Vec::new();
+            if whole.starts_with('+') {
+                parts.push(self.rules.plus.to_string());
+            }
+            let groups: Vec<String> = GROUP
+                .find_iter(whole)
+                .map(|g| self.digits(g.as_str()))
+                .collect();
+            parts.push(groups.join(", "));
+            Some(parts.join(" "))
+        })
+    }
+
+    /// "call 911" is read digit by digit; "Room 112" and "2.000" are numbers
+    fn expand_emergency_numbers(&self, text: &str) -> String {
+        static RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"(?i)(?-u:\b)(call|calling|called|dial|dialing|dialled|dialed|ring)(\s+)(911|999|112|000)(?-u:\b)")
+                .unwrap()
+        });
+        replace_checked(&RE, text, |caps, _| {
+            if continues_number(text, caps.get(0).unwrap().end()) {
+                return None;
+            }
+            Some(format!("{}{}{}", &caps[1], &caps[2], self.digits(&caps[3])))
+        })
+    }
+
+    fn expand_currencies(&self, text: &str) -> String {
+        // Prefixed: US$5, C$2.5bn, £800m, EUR 1bn, SR3mn
+        // The magnitude must be a whole word, so "$5 more" keeps its "more"
+        static PREFIXED: AmountRegex = amount_regex!(
+            r"([-−])?(US\$|CA\$|C\$|AU\$|A\$|NZ\$|HK\$|S\$|R\$|[$£€₹¥₩]|(?-u:\b)(?:USD|CAD|AUD|NZD|HKD|SGD|GBP|EUR|BRL|INR|JPY|CNY|RMB|KRW|SAR|SR))\s?({amount})(?:\s?(trillion|billion|million|thousand|crore|lakh|bn|mn|tn|m|b|k)(?-u:\b))?"
+        );
+        // Suffixed: 5 €, 10,50 €, 3000원
+        static SUFFIXED: AmountRegex = amount_regex!(r"([-−])?(?-u:\b)({amount})\s?([$£€₹¥₩원])");
+
+        let text = replace_checked(PREFIXED.get(self.rules.decimal_comma), text, |caps, next| {
+            if is_word_char(next) {
+                return None;
+            }
+            let currency = currency_for(caps[2].trim())?;
+            let money = self.spell_money(currency, &caps[3], caps.get(4).map(|m| m.as_str()));
+            Some(format!("{}{}", self.sign(text, caps.get(1)), money))
+        });
+        replace_checked(SUFFIXED.get(self.rules.decimal_comma), &text, |caps, _| {
+            let currency = currency_for(&caps[3])?;
+            let money = self.spell_money(currency, &caps[2], None);
+            Some(format!("{}{}", self.sign(&text, caps.get(1)), money))
+        })
+    }
+
+    /// An amount counting the noun after it: Spanish "uno" becomes "un"
+    /// ("un dólar", "veintiún millones")
+    fn spell_count(&self, amount: &str) -> String {
+        let spoken = self.spell_amount(amount);
+        if self.lang == "es" && !amount.contains(['.', ',']) {
+            es_apocope(spoken)
+        } else {
+            spoken
+        }
+    }
+
+    fn spell_money(&self, currency: Currency, amount: &str, magnitude: Option<&str>) -> String {
+        let rules = self.rules;
+
+        if let Some(mag) = magnitude.and_then(magnitude_for) {
+            let plural = !Self::is_one(amount);
+            let spoken = format!("{} {}", self.spell_count(amount), (rules.magnitude)(mag, plural));
+            let currency = (rules.currency)(currency, true);
+            return if matches!(mag, Magnitude::Thousand | Magnitude::Lakh | Magnitude::Crore) {
+                format!("{} {}", spoken, currency)
+            } else {
+                self.join_of(&spoken, " ", currency)
+            };
+        }
+
+        // Two decimals read as major and minor units when the currency has them
+        let split = amount
+            .find(['.', ','])
+            .filter(|&pos| amount.len() - pos - 1 == 2);
+        if let Some(pos) = split {
+            let (whole, cents) = (&amount[..pos], &amount[pos + 1..]);
+            if let Some(minor) = (rules.minor_unit)(currency, cents != "01") {
+                let major = format!(
+                    "{} {}",
+                    self.spell_count(whole),
+                    (rules.currency)(currency, !Self::is_one(whole))
+                );
+                if cents == "00" {
+                    return major;
+                }
+                return format!(
+                    "{}{}{} {}",
+                    major,
+                    rules.and,
+                    self.cardinal_str(cents.trim_start_matches('0')),
+                    minor
+                );
+            }
+        }
+
+        format!(
+            "{} {}",
+            self.spell_count(amount),
+            (rules.currency)(currency, !Self::is_one(amount))
+        )
+    }
+
+    fn expand_units(&self, text: &str) -> String {
+        // The sign is part of the match so "-5°C" keeps its minus
+        static RE: AmountRegex = amount_regex!(
+            r"([-−])?(?-u:\b)({amount})\s?(km/h|kph|kmh|mph|m/s|km|cm|mm|kg|mg|lbs|lb|mi|m|g|h|°C|°F|℃|℉)"
+        );
+        replace_checked(RE.get(self.rules.decimal_comma), text, |caps, next| {
+            if next.is_some_and(|c| c.is_alphabetic()) {
+                return None;
+            }
+            let unit = match &caps[3] {
+                "km/h" | "kph" | "kmh" => Unit::KilometerPerHour,
+                "mph" => Unit::MilePerHour,
+                "m/s" => Unit::MeterPerSecond,
+                "km" => Unit::Kilometer,
+                "cm" => Unit::Centimeter,
+                "mm" => Unit::Millimeter,
+                "kg" => Unit::Kilogram,
+                "mg" => Unit::Milligram,
+                "lb" | "lbs" => Unit::PoundWeight,
+                "mi" => Unit::Mile,
+                "m" => Unit::Meter,
+                "g" => Unit::Gram,
+                "h" => Unit::Hour,
+                "°C" | "℃" => Unit::Celsius,
+                _ => Unit::Fahrenheit,
+            };
+            let template = (self.rules.unit)(unit, !Self::is_one(&caps[2]));
+            Some(format!("{}{}", self.sign(text, caps.get(1)), template.replace("{}", &self.spell_amount(&caps[2]))))
+        })
+    }
+
+    fn expand_magnitudes(&self, text: &str) -> String {
+        static RE: AmountRegex = amount_regex!(r"(?-u:\b)({amount})\s?(M|mn|B|bn|tn)(?:(\s+)(\p{{L}}+))?");
+        replace_checked(RE.get(self.rules.decimal_comma), text, |caps, next| {
+            if caps.get(3).is_none() && next.is_some_and(|c| c.is_alphabetic()) {
+                return None;
+            }
+            let mag = magnitude_for(&caps[2])?;
+            let plural = !Self::is_one(&caps[1]);
+            let spoken = if self.lang == "es" && !plural {
+                "un".to_string()
+            } else {
+                self.spell_amount(&caps[1])
+            };
+            let spoken = format!("{} {}", spoken, (self.rules.magnitude)(mag, plural));
+            Some(match (caps.get(3), caps.get(4)) {
+                (Some(space), Some(noun)) if is_noun(noun.as_str()) => {
+                    self.join_of(&spoken, space.as_str(), noun.as_str())
+                }
+                (Some(space), Some(word)) => format!("{}{}{}", spoken, space.as_str(), word.as_str()),
+                _ => spoken,
+            })
+        })
+    }
+
+    fn expand_percentages(&self, text: &str) -> String {
+        static RE: AmountRegex = amount_regex!(r"([-−])?(?-u:\b)({amount})\s?%");
+        RE.get(self.rules.decimal_comma)
+            .replace_all(text, |caps: &Captures| {
+                format!("{}{} {}", self.sign(text, caps.get(1)), self.spell_amount(&caps[2]), self.rules.percent)
+            })
+            .to_string()
+    }
+
+    fn expand_ordinals(&self, text: &str) -> String {
+        static EN_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?-u:\b)(\d+)(st|nd|rd|th)(?-u:\b)").unwrap());
+        static ES_PT_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?-u:\b)(\d+)\.?([ºª°])").unwrap());
+        static FR_RE: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"(?-u:\b)(\d+)(ère|re|er|ème|eme|e)(?-u:\b)").unwrap());
+        static KO_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(\d+)\s?번째").unwrap());
+
+        let rules = self.rules;
+        match self.lang {
+            "en" => replace_checked(&EN_RE, text, |caps, _| {
+                caps[1].parse().ok().map(|n| (rules.ordinal)(n, false))
+            }),
+            "es" | "pt" => replace_checked(&ES_PT_RE, text, |caps, _| {
+                caps[1].parse().ok().map(|n| (rules.ordinal)(n, &caps[2] == "ª"))
+            }),
+            "fr" => replace_checked(&FR_RE, text, |caps, _| {
+                let feminine = matches!(&caps[2], "ère" | "re");
+                caps[1].parse().ok().map(|n| (rules.ordinal)(n, feminine))
+            }),
+            "ko" => replace_checked(&KO_RE, text, |caps, _| {
+                caps[1].parse().ok().map(|n| (rules.ordinal)(n, false))
+            }),
+            _ => text.to_string(),
+        }
+    }
+
+    fn expand_ranges(&self, text: &str) -> String {
+        static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?-u:\b)(\d+)\s?[-–]\s?(\d+)(?-u:\b)").unwrap());
+        RE.replace_all(text, |caps: &Captures| {
+            format!("{} {} {}", &caps[1], self.rules.range, &caps[2])
+        })
+        .to_string()
+    }
+
+    /// English year reading, as in the extension: 1998 -> nineteen ninety
+    /// eight, 2005 -> two thousand five, 1905 -> nineteen oh five
+    fn expand_years(&self, text: &str) -> String {
+        static DECADES: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?-u:\b)(1[0-9]|20)([0-9])0s(?-u:\b)").unwrap());
+        // A counted noun or unit after the number makes it a quantity
+        static YEARS: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"(?-u:\b)(19|20)(\d{2})(?-u:\b)(?:\s+(\p{L}+))?").unwrap());
+
+        let text = DECADES.replace_all(text, |caps: &Captures| {
+            let century: u64 = caps[1].parse().unwrap();
+            match (century, caps[2].parse::<u64>().unwrap()) {
+                // 2000s, 1900s
+                (20, 0) => "two thousands".to_string(),
+                (_, 0) => format!("{} hundreds", en_cardinal(century)),
+                (_, tens) => {
+                    let decade = en_cardinal(tens * 10);
+                    let plural = match decade.strip_suffix('y') {
+                        Some(stem) => format!("{}ies", stem),
+                        None => format!("{}s", decade),
+                    };
+                    format!("{} {}", en_cardinal(century), plural)
+                }
+            }
+        });
+
+        replace_checked(&YEARS, &text, |caps, next| {
+            if caps.get(3).is_none() && next == Some('s') {
+                return None;
+            }
+            if caps.get(3).is_some_and(|word| en_counts(word.as_str())) {
+                return None;
+            }
+            let century: u64 = caps[1].parse().unwrap();
+            let rest: u64 = caps[2].parse().unwrap();
+            let year = match (century, rest) {
+                (20, 0) => "two thousand".to_string(),
+                (20, 1..=9) => format!("two thousand {}", en_cardinal(rest)),
+                (19, 0) => "nineteen hundred".to_string(),
+                (19, 1..=9) => format!("nineteen oh {}", en_cardinal(rest)),
+                _ => format!("{} {}", en_cardinal(century), en_cardinal(rest)),
+            };
+            // Keep the word after the year as it was
+            Some(format!("{}{}", year, &caps[0][4..]))
+        })
+    }
+
+    fn expand_titles(&self, text: &str) -> String {
+        fn titles_regex(rules: &LangRules) -> Regex {
+            let titles: Vec<String> = rules.titles.iter().map(|(abbr, _)| regex::escape(abbr)).collect();
+            Regex::new(&format!(r"(?-u:\b)({})(\s)", titles.join("|"))).unwrap()
+        }
+        static EN_RE: LazyLock<Regex> = LazyLock::new(|| titles_regex(&EN));
+        static ES_RE: LazyLock<Regex> = LazyLock::new(|| titles_regex(&ES));
+        static PT_RE: LazyLock<Regex> = LazyLock::new(|| titles_regex(&PT));
+        static FR_RE: LazyLock<Regex> = LazyLock::new(|| titles_regex(&FR));
+
+        let re = match self.lang {
+            "en" => &EN_RE,
+            "es" => &ES_RE,
+            "pt" => &PT_RE,
+            "fr" => &FR_RE,
+            _ => return text.to_string(),
+        };
+        re.replace_all(text, |caps: &Captures| {
+            let full = self.rules.titles.iter().find(|(abbr, _)| *abbr == &caps[1]).map_or(&caps[1], |(_, full)| full);
+            format!("{}{}", full, &caps[2])
+        })
+        .to_string()
+    }
+
+    fn expand_negatives(&self, text: &str) -> String {
+        static RE: AmountRegex = amount_regex!(r"(^|[\s(])[-−]({amount})(?-u:\b)");
+        RE.get(self.rules.decimal_comma)
+            .replace_all(text, |caps: &Captures| {
+                format!("{}{} {}", &caps[1], self.rules.minus, self.spell_amount(&caps[2]))
+            })
+            .to_string()
+    }
+
+    fn expand_numbers(&self, text: &str) -> String {
+        // The word after a number decides "veintiún años" and "um milhão de pessoas"
+        static RE: AmountRegex = amount_regex!(r"(?-u:\b)({amount})(?-u:\b)(?:(\s+)(\p{{L}}+))?");
+        replace_checked(RE.get(self.rules.decimal_comma), text, |caps, _| {
+            let number = caps.get(1).unwrap();
+            if in_version(text, number) {
+                return None;
+            }
+            let mut spoken = self.spell_amount(number.as_str());
+            let (space, word) = match (caps.get(2), caps.get(3)) {
+                (Some(space), Some(word)) => (space.as_str(), word.as_str()),
+                _ => return Some(spoken),
+            };
+            if !is_noun(word) {
+                return Some(format!("{}{}{}", spoken, space, word));
+            }
+            // "uno" becomes "un" before the noun it counts ("21 años", "21 mil")
+            if self.lang == "es" {
+                spoken = es_apocope(spoken);
+            }
+            let round_millions = number.as_str().parse::<u64>().is_ok_and(|n| n >= 1_000_000 && n.is_multiple_of(1_000_000));
+            Some(if round_millions {
+                self.join_of(&spoken, space, word)
+            } else {
+                format!("{}{}{}", spoken, space, word)
+            })
+        })
+    }
+}
+
+fn currency_for(symbol: &str) -> Option<Currency> {
+    Some(match symbol.to_uppercase().as_str() {
+        "$" => Currency::Dollar,
+        "US$" | "USD" => Currency::UsDollar,
+        "C$" | "CA$" | "CAD" => Currency::CanadianDollar,
+        "A$" | "AU$" | "AUD" => Currency::AustralianDollar,
+        "NZ$" | "NZD" => Currency::NewZealandDollar,
+        "HK$" | "HKD" => Currency::HongKongDollar,
+        "S$" | "SGD" => Currency::SingaporeDollar,
+        "R$" | "BRL" => Currency::Real,
+        "£" => Currency::Pound,
+        "GBP" => Currency::BritishPound,
+        "€" | "EUR" => Currency::Euro,
+        "₹" => Currency::Rupee,
+        "INR" => Currency::IndianRupee,
+        "¥" => Currency::Yen,
+        "JPY" => Currency::JapaneseYen,
+        "CNY" | "RMB" => Currency::Yuan,
+        "₩" | "원" => Currency::Won,
+        "KRW" => Currency::KoreanWon,
+        "SR" | "SAR" => Currency::Riyal,
+        _ => return None,
+    })
+}
+
+fn magnitude_for(suffix: &str) -> Option<Magnitude> {
+    Some(match suffix.to_lowercase().as_str() {
+        "k" | "thousand" => Magnitude::Thousand,
+        "m" | "mn" | "million" => Magnitude::Million,
+        "b" | "bn" | "billion" => Magnitude::Billion,
+        "tn" | "trillion" => Magnitude::Trillion,
+        "lakh" => Magnitude::Lakh,
+        "crore" => Magnitude::Crore,
+        _ => return None,
+    })
+}
+
+fn pick(plural: bool, one: &'static str, many: &'static str) -> &'static str {
+    if plural { many } else { one }
+}
+
+// ============================================================================
+// English
+// ============================================================================
+
+const EN_UNITS: [&str; 20] = [
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine", "ten",
+    "eleven", "twelve", "thirteen", "fourteen", "fifteen", "sixteen", "seventeen",
+    "eighteen", "nineteen",
+];
+
+const EN_TENS: [&str; 10] = [
+    "", "", "twenty", "thirty", "forty", "fifty", "sixty", "seventy", "eighty", "ninety",
+];
+
+/// Same output as NumberUtils.convert in the extension (no "and", no hyphens)
+fn en_cardinal(n: u64) -> String {
+    fn join(head: String, unit: &str, scale: u64, n: u64) -> String {
+        let rest = n % scale;
+        if rest == 0 {
+            format!("{} {}", head, unit)
+        } else {
+            format!("{} {} {}", head, unit, en_cardinal(rest))
+        }
+    }
+
+    match n {
+        0..=19 => EN_UNITS[n as usize].to_string(),
+        20..=99 => {
+            let tens = EN_TENS[(n / 10) as usize];
+            if n.is_multiple_of(10) {
+                tens.to_string()
+            } else {
+                format!("{} {}", tens, EN_UNITS[(n % 10) as usize])
+            }
+        }
+        100..=999 => join(EN_UNITS[(n / 100) as usize].to_string(), "hundred", 100, n),
+        1_000..=999_999 => join(en_cardinal(n / 1_000), "thousand", 1_000, n),
+        1_000_000..=999_999_999 => join(en_cardinal(n / 1_000_000), "million", 1_000_000, n),
+        1_000_000_000..=999_999_999_999 => {
+            join(en_cardinal(n / 1_000_000_000), "billion", 1_000_000_000, n)
+        }
+        _ => join(en_cardinal(n / 1_000_000_000_000), "trillion", 1_000_000_000_000, n),
+    }
+}
+
+fn en_ordinal(n: u64, _feminine: bool) -> String {
+    let cardinal = en_cardinal(n);
+    let (head, last) = match cardinal.rfind(' ') {
+        Some(pos) => cardinal.split_at(pos + 1),
+        None => ("", cardinal.as_str()),
+    };
+    let last = match last {
+        "one" => "first".to_string(),
+        "two" => "second".to_string(),
+        "three" => "third".to_string(),
+        "five" => "fifth".to_string(),
+        "eight" => "eighth".to_string(),
+        "nine" => "ninth".to_string(),
+        "twelve" => "twelfth".to_string(),
+        word => match word.strip_suffix('y') {
+            Some(stem) => format!("{}ieth", stem),
+            None => format!("{}th", word),
+        },
+    };
+    format!("{}{}", head, last)
+}
+
+fn en_magnitude(mag: Magnitude, _plural: bool) -> &'static str {
+    match mag {
+        Magnitude::Thousand => "thousand",
+        Magnitude::Million => "million",
+        Magnitude::Billion => "billion",
+        Magnitude::Trillion => "trillion",
+        Magnitude::Lakh => "lakh",
+        Magnitude::Crore => "crore",
+    }
+}
+
+fn en_currency(currency: Currency, plural: bool) -> &'static str {
+    match currency {
+        Currency::Dollar => pick(plural, "dollar", "dollars"),
+        Currency::UsDollar => pick(plural, "US dollar", "US dollars"),
+        Currency::CanadianDollar => pick(plural, "Canadian dollar", "Canadian dollars"),
+        Currency::AustralianDollar => pick(plural, "Australian dollar", "Australian dollars"),
+        Currency::NewZealandDollar => pick(plural, "New Zealand dollar", "New Zealand dollars"),
+        Currency::HongKongDollar => pick(plural, "Hong Kong dollar", "Hong Kong dollars"),
+        Currency::SingaporeDollar => pick(plural, "Singapore dollar", "Singapore dollars"),
+        Currency::Real => pick(plural, "Brazilian real", "Brazilian reais"),
+        Currency::Pound => pick(plural, "pound", "pounds"),
+        Currency::BritishPound => pick(plural, "British pound", "British pounds"),
+        Currency::Euro => pick(plural, "euro", "euros"),
+        Currency::Rupee => pick(plural, "rupee", "rupees"),
+        Currency::IndianRupee => pick(plural, "Indian rupee", "Indian rupees"),
+        Currency::Yen => "yen",
+        Currency::JapaneseYen => "Japanese yen",
+        Currency::Yuan => "Chinese yuan",
+        Currency::Won => "won",
+        Currency::KoreanWon => "South Korean won",
+        Currency::Riyal => pick(plural, "Saudi riyal", "Saudi riyals"),
+    }
+}
+
+fn en_minor_unit(currency: Currency, plural: bool) -> Option<&'static str> {
+    match currency {
+        Currency::Pound | Currency::BritishPound => Some(pick(plural, "penny", "pence")),
+        Currency::Rupee | Currency::IndianRupee => Some(pick(plural, "paisa", "paise")),
+        Currency::Yen | Currency::JapaneseYen | Currency::Won | Currency::KoreanWon
+        | Currency::Yuan | Currency::Riyal => None,
+        _ => Some(pick(plural, "cent", "cents")),
+    }
+}
+
+fn en_unit(unit: Unit, plural: bool) -> &'static str {
+    match unit {
+        Unit::Kilometer => pick(plural, "{} kilometer", "{} kilometers"),
+        Unit::Meter => pick(plural, "{} meter", "{} meters"),
+        Unit::Centimeter => pick(plural, "{} centimeter", "{} centimeters"),
+        Unit::Millimeter => pick(plural, "{} millimeter", "{} millimeters"),
+        Unit::Kilogram => pick(plural, "{} kilogram", "{} kilograms"),
+        Unit::Gram => pick(plural, "{} gram", "{} grams"),
+        Unit::Milligram => pick(plural, "{} milligram", "{} milligrams"),
+        Unit::PoundWeight => pick(plural, "{} pound", "{} pounds"),
+        Unit::Mile => pick(plural, "{} mile", "{} miles"),
+        Unit::Hour => pick(plural, "{} hour", "{} hours"),
+        Unit::KilometerPerHour => pick(plural, "{} kilometer per hour", "{} kilometers per hour"),
+        Unit::MilePerHour => pick(plural, "{} mile per hour", "{} miles per hour"),
+        Unit::MeterPerSecond => pick(plural, "{} meter per second", "{} meters per second"),
+        Unit::Celsius => pick(plural, "{} degree Celsius", "{} degrees Celsius"),
+        Unit::Fahrenheit => pick(plural, "{} degree Fahrenheit", "{} degrees Fahrenheit"),
+    }
+}
+
+static EN: LangRules = LangRules {
+    decimal_comma: false,
+    cardinal: en_cardinal,
+    ordinal: en_ordinal,
+    point: "point",
+    minus: "minus",
+    plus: "plus",
+    percent: "percent",
+    range: "to",
+    and: " and ",
+    of: "",
+    magnitude: en_magnitude,
+    currency: en_currency,
+    minor_unit: en_minor_unit,
+    unit: en_unit,
+    titles: &[
+        ("Prof.", "Professor"),
+        ("Dr.", "Doctor"),
+        ("Mrs.", "Missus"),
+        ("Mr.", "Mister"),
+        ("Ms.", "Miss"),
+        ("approx.", "approximately"),
+        ("vs.", "versus"),
+    ],
+};
+
+// ============================================================================
+// Spanish
+// ============================================================================
+
+const ES_UNITS: [&str; 30] = [
+    "cero", "uno", "dos", "tres", "cuatro", "cinco", "seis", "siete", "ocho", "nueve", "diez",
+    "once", "doce", "trece", "catorce", "quince", "dieciséis", "diecisiete", "dieciocho",
+    "diecinueve", "veinte", "veintiuno", "veintidós", "veintitrés", "veinticuatro",
+    "veinticinco", "veintiséis", "veintisiete", "veintiocho", "veintinueve",
+];
+
+const ES_TENS: [&str; 10] = [
+    "", "", "", "treinta", "cuarenta", "cincuenta", "sesenta", "setenta", "ochenta", "noventa",
+];
+
+const ES_HUNDREDS: [&str; 10] = [
+    "", "ciento", "doscientos", "trescientos", "cuatrocientos", "quinientos", "seiscientos",
+    "setecientos", "ochocientos", "novecientos",
+];
+
+/// "uno" becomes "un" in front of a noun ("veintiún mil", "un millón")
+fn es_apocope(words: String) -> String {
+    if let Some(stem) = words.strip_suffix("veintiuno") {
+        format!("{}veintiún", stem)
+    } else if let Some(stem) = words.strip_suffix("uno") {
+        format!("{}un", stem)
+    } else {
+        words
+    }
+}
+
+fn es_cardinal(n: u64) -> String {
+    fn with_rest(head: String, rest: u64) -> String {
+        if rest == 0 { head } else { format!("{} {}", head, es_cardinal(rest)) }
+    }
+
+    match n {
+        0..=29 => ES_UNITS[n as usize].to_string(),
+        30..=99 => {
+            let tens = ES_TENS[(n / 10) as usize];
+            if n.is_multiple_of(10) {
+                tens.to_string()
+            } else {
+                format!("{} y {}", tens, ES_UNITS[(n % 10) as usize])
+            }
+        }
+        100 => "cien".to_string(),
+        101..=999 => with_rest(ES_HUNDREDS[(n / 100) as usize].to_string(), n % 100),
+        1_000..=999_999 => {
+            let thousands = n / 1_000;
+            let head = if thousands == 1 {
+                "mil".to_string()
+            } else {
+                format!("{} mil", es_apocope(es_cardinal(thousands)))
+            };
+            with_rest(head, n % 1_000)
+        }
+        1_000_000..=999_999_999_999 => {
+            let millions = n / 1_000_000;
+            let head = if millions == 1 {
+                "un millón".to_string()
+            } else {
+                format!("{} millones", es_apocope(es_cardinal(millions)))
+            };
+            with_rest(head, n % 1_000_000)
+        }
+        _ => {
+            let billions = n / 1_000_000_000_000;
+            let head = if billions == 1 {
+                "un billón".to_string()
+            } else {
+                format!("{} billones", es_apocope(es_cardinal(billions)))
+            };
+            with_rest(head, n % 1_000_000_000_000)
+        }
+    }
+}
+
+const ES_ORDINALS: [&str; 11] = [
+    "", "primero", "segundo", "tercero", "cuarto", "quinto", "sexto", "séptimo", "octavo",
+    "noveno", "décimo",
+];
+
+fn es_ordinal(n: u64, feminine: bool) -> String {
+    match ES_ORDINALS.get(n as usize) {
+        Some(word) if n > 0 => {
+            if feminine {
+                format!("{}a", &word[..word.len() - 1])
+            } else {
+                word.to_string()
+            }
+        }
+        // Larger ordinals are commonly read as cardinals
+        _ => es_cardinal(n),
+    }
+}
+
+fn es_magnitude(mag: Magnitude, plural: bool) -> &'static str {
+    match mag {
+        Magnitude::Thousand => "mil",
+        Magnitude::Million => pick(plural, "millón", "millones"),
+        Magnitude::Billion => "mil millones",
+        Magnitude::Trillion => pick(plural, "billón", "billones"),
+        Magnitude::Lakh => pick(plural, "lakh", "lakhs"),
+        Magnitude::Crore => pick(plural, "crore", "crores"),
+    }
+}
+
+fn es_currency(currency: Currency, plural: bool) -> &'static str {
+    match currency {
+        Currency::Dollar => pick(plural, "dólar", "dólares"),
+        Currency::UsDollar => pick(plural, "dólar estadounidense", "dólares estadounidenses"),
+        Currency::CanadianDollar => pick(plural, "dólar canadiense", "dólares canadienses"),
+        Currency::AustralianDollar => pick(plural, "dólar australiano", "dólares australianos"),
+        Currency::NewZealandDollar => pick(plural, "dólar neozelandés", "dólares neozelandeses"),
+        Currency::HongKongDollar => pick(plural, "dólar de Hong Kong", "dólares de Hong Kong"),
+        Currency::SingaporeDollar => pick(plural, "dólar de Singapur", "dólares de Singapur"),
+        Currency::Real => pick(plural, "real brasileño", "reales brasileños"),
+        Currency::Pound => pick(plural, "libra", "libras"),
+        Currency::BritishPound => pick(plural, "libra esterlina", "libras esterlinas"),
+        Currency::Euro => pick(plural, "euro", "euros"),
+        Currency::Rupee => pick(plural, "rupia", "rupias"),
+        Currency::IndianRupee => pick(plural, "rupia india", "rupias indias"),
+        Currency::Yen => pick(plural, "yen", "yenes"),
+        Currency::JapaneseYen => pick(plural, "yen japonés", "yenes japoneses"),
+        Currency::Yuan => pick(plural, "yuan", "yuanes"),
+        Currency::Won => pick(plural, "won", "wones"),
+        Currency::KoreanWon => pick(plural, "won surcoreano", "wones surcoreanos"),
+        Currency::Riyal => pick(plural, "riyal saudí", "riyales saudíes"),
+    }
+}
+
+fn es_minor_unit(currency: Currency, plural: bool) -> Option<&'static str> {
+    match currency {
+        Currency::Euro => Some(pick(plural, "céntimo", "céntimos")),
+        Currency::Pound | Currency::BritishPound => Some(pick(plural, "penique", "peniques")),
+        Currency::Rupee | Currency::IndianRupee => Some("paisas"),
+        Currency::Yen | Currency::JapaneseYen | Currency::Won | Currency::KoreanWon
+        | Currency::Yuan | Currency::Riyal => None,
+        _ => Some(pick(plural, "centavo", "centavos")),
+    }
+}
+
+fn es_unit(unit: Unit, plural: bool) -> &'static str {
+    match unit {
+        Unit::Kilometer => pick(plural, "{} kilómetro", "{} kilómetros"),
+        Unit::Meter => pick(plural, "{} metro", "{} metros"),
+        Unit::Centimeter => pick(plural, "{} centímetro", "{} centímetros"),
+        Unit::Millimeter => pick(plural, "{} milímetro", "{} milímetros"),
+        Unit::Kilogram => pick(plural, "{} kilogramo", "{} kilogramos"),
+        Unit::Gram => pick(plural, "{} gramo", "{} gramos"),
+        Unit::Milligram => pick(plural, "{} miligramo", "{} miligramos"),
+        Unit::PoundWeight => pick(plural, "{} libra", "{} libras"),
+        Unit::Mile => pick(plural, "{} milla", "{} millas"),
+        Unit::Hour => pick(plural, "{} hora", "{} horas"),
+        Unit::KilometerPerHour => pick(plural, "{} kilómetro por hora", "{} kilómetros por hora"),
+        Unit::MilePerHour => pick(plural, "{} milla por hora", "{} millas por hora"),
+        Unit::MeterPerSecond => pick(plural, "{} metro por segundo", "{} metros por segundo"),
+        Unit::Celsius => pick(plural, "{} grado Celsius", "{} grados Celsius"),
+        Unit::Fahrenheit => pick(plural, "{} grado Fahrenheit", "{} grados Fahrenheit"),
+    }
+}
+
+static ES: LangRules = LangRules {
+    decimal_comma: true,
+    cardinal: es_cardinal,
+    ordinal: es_ordinal,
+    point: "coma",
+    minus: "menos",
+    plus: "más",
+    percent: "por ciento",
+    range: "a",
+    and: " con ",
+    of: "de ",
+    magnitude: es_magnitude,
+    currency: es_currency,
+    minor_unit: es_minor_unit,
+    unit: es_unit,
+    titles: &[
+        ("Sra.", "señora"),
+        ("Sr.", "señor"),
+        ("Dra.", "doctora"),
+        ("Dr.", "doctor"),
+    ],
+};
+
+// ============================================================================
+// Portuguese
+// ============================================================================
+
+const PT_UNITS: [&str; 20] = [
+    "zero", "um", "dois", "três", "quatro", "cinco", "seis", "sete", "oito", "nove", "dez",
+    "onze", "doze", "treze", "catorze", "quinze", "dezesseis", "dezessete", "dezoito",
+    "dezenove",
+];
+
+const PT_TENS: [&str; 10] = [
+    "", "", "vinte", "trinta", "quarenta", "cinquenta", "sessenta", "setenta", "oitenta",
+    "noventa",
+];
+
+const PT_HUNDREDS: [&str; 10] = [
+    "", "cento", "duzentos", "trezentos", "quatrocentos", "quinhentos", "seiscentos",
+    "setecentos", "oitocentos", "novecentos",
+];
+
+fn pt_cardinal(n: u64) -> String {
+    /// "mil e quinhentos", "mil duzentos e trinta": "e" only before a
+    /// remainder below one hundred or a round hundred
+    fn with_rest(head: String, rest: u64) -> String {
+        if rest == 0 {
+            head
+        } else if rest < 100 || rest.is_multiple_of(100) {
+            format!("{} e {}", head, pt_cardinal(rest))
+        } else {
+            format!("{} {}", head, pt_cardinal(rest))
+        }
+    }
+
+    fn scale(n: u64, size: u64, one: &str, many: &str) -> String {
+        let count = n / size;
+        let head = if count == 1 {
+            format!("um {}", one)
+        } else {
+            format!("{} {}", pt_cardinal(count), many)
+        };
+        with_rest(head, n % size)
+    }
+
+    match n {
+        0..=19 => PT_UNITS[n as usize].to_string(),
+        20..=99 => {
+            let tens = PT_TENS[(n / 10) as usize];
+            if n.is_multiple_of(10) {
+                tens.to_string()
+            } else {
+                format!("{} e {}", tens, PT_UNITS[(n % 10) as usize])
+            }
+        }
+        100 => "cem".to_string(),
+        101..=999 => {
+            let head = PT_HUNDREDS[(n / 100) as usize];
+            if n.is_multiple_of(100) {
+                head.to_string()
+            } else {
+                format!("{} e {}", head, pt_cardinal(n % 100))
+            }
+        }
+        1_000..=999_999 => {
+            let thousands = n / 1_000;
+            let head = if thousands == 1 {
+                "mil".to_string()
+            } else {
+                format!("{} mil", pt_cardinal(thousands))
+            };
+            with_rest(head, n % 1_000)
+        }
+        1_000_000..=999_999_999 => scale(n, 1_000_000, "milhão", "milhões"),
+        1_000_000_000..=999_999_999_999 => scale(n, 1_000_000_000, "bilhão", "bilhões"),
+        _ => scale(n, 1_000_000_000_000, "trilhão", "trilhões"),
+    }
+}
+
+const PT_ORDINALS: [&str; 11] = [
+    "", "primeiro", "segundo", "terceiro", "quarto", "quinto", "sexto", "sétimo", "oitavo",
+    "nono", "décimo",
+];
+
+fn pt_ordinal(n: u64, feminine: bool) -> String {
+    match PT_ORDINALS.get(n as usize) {
+        Some(word) if n > 0 => {
+            if feminine {
+                format!("{}a", &word[..word.len() - 1])
+            } else {
+                word.to_string()
+            }
+        }
+        _ => pt_cardinal(n),
+    }
+}
+
+fn pt_magnitude(mag: Magnitude, plural: bool) -> &'static str {
+    match mag {
+        Magnitude::Thousand => "mil",
+        Magnitude::Million => pick(plural, "milhão", "milhões"),
+        Magnitude::Billion => pick(plural, "bilhão", "bilhões"),
+        Magnitude::Trillion => pick(plural, "trilhão", "trilhões"),
+        Magnitude::Lakh => "lakh",
+        Magnitude::Crore => "crore",
+    }
+}
+
+fn pt_currency(currency: Currency, plural: bool) -> &'static str {
+    match currency {
+        Currency::Dollar => pick(plural, "dólar", "dólares"),
+        Currency::UsDollar => pick(plural, "dólar americano", "dólares americanos"),
+        Currency::CanadianDollar => pick(plural, "dólar canadense", "dólares canadenses"),
+        Currency::AustralianDollar => pick(plural, "dólar australiano", "dólares australianos"),
+        Currency::NewZealandDollar => pick(plural, "dólar neozelandês", "dólares neozelandeses"),
+        Currency::HongKongDollar => pick(plural, "dólar de Hong Kong", "dólares de Hong Kong"),
+        Currency::SingaporeDollar => pick(plural, "dólar de Singapura", "dólares de Singapura"),
+        Currency::Real => pick(plural, "real", "reais"),
+        Currency::Pound => pick(plural, "libra", "libras"),
+        Currency::BritishPound => pick(plural, "libra esterlina", "libras esterlinas"),
+        Currency::Euro => pick(plural, "euro", "euros"),
+        Currency::Rupee => pick(plural, "rupia", "rupias"),
+        Currency::IndianRupee => pick(plural, "rupia indiana", "rupias indianas"),
+        Currency::Yen => pick(plural, "iene", "ienes"),
+        Currency::JapaneseYen => pick(plural, "iene japonês", "ienes japoneses"),
+        Currency::Yuan => pick(plural, "yuan", "yuans"),
+        Currency::Won => pick(plural, "won", "wons"),
+        Currency::KoreanWon => pick(plural, "won sul-coreano", "wons sul-coreanos"),
+        Currency::Riyal => pick(plural, "rial saudita", "riais sauditas"),
+    }
+}
+
+fn pt_minor_unit(currency: Currency, plural: bool) -> Option<&'static str> {
+    match currency {
+        Currency::Euro => Some(pick(plural, "cêntimo", "cêntimos")),
+        Currency::Pound | Currency::BritishPound => Some(pick(plural, "pêni", "pence")),
+        Currency::Rupee | Currency::IndianRupee => Some("paisas"),
+        Currency::Yen | Currency::JapaneseYen | Currency::Won | Currency::KoreanWon
+        | Currency::Yuan | Currency::Riyal => None,
+        _ => Some(pick(plural, "centavo", "centavos")),
+    }
+}
+
+fn pt_unit(unit: Unit, plural: bool) -> &'static str {
+    match unit {
+        Unit::Kilometer => pick(plural, "{} quilômetro", "{} quilômetros"),
+        Unit::Meter => pick(plural, "{} metro", "{} metros"),
+        Unit::Centimeter => pick(plural, "{} centímetro", "{} centímetros"),
+        Unit::Millimeter => pick(plural, "{} milímetro", "{} milímetros"),
+        Unit::Kilogram => pick(plural, "{} quilograma", "{} quilogramas"),
+        Unit::Gram => pick(plural, "{} grama", "{} gramas"),
+        Unit::Milligram => pick(plural, "{} miligrama", "{} miligramas"),
+        Unit::PoundWeight => pick(plural, "{} libra", "{} libras"),
+        Unit::Mile => pick(plural, "{} milha", "{} milhas"),
+        Unit::Hour => pick(plural, "{} hora", "{} horas"),
+        Unit::KilometerPerHour => pick(plural, "{} quilômetro por hora", "{} quilômetros por hora"),
+        Unit::MilePerHour => pick(plural, "{} milha por hora", "{} milhas por hora"),
+        Unit::MeterPerSecond => pick(plural, "{} metro por segundo", "{} metros por segundo"),
+        Unit::Celsius => pick(plural, "{} grau Celsius", "{} graus Celsius"),
+        Unit::Fahrenheit => pick(plural, "{} grau Fahrenheit", "{} graus Fahrenheit"),
+    }
+}
+
+static PT: LangRules = LangRules {
+    decimal_comma: true,
+    cardinal: pt_cardinal,
+    ordinal: pt_ordinal,
+    point: "vírgula",
+    minus: "menos",
+    plus: "mais",
+    percent: "por cento",
+    range: "a",
+    and: " e ",
+    of: "de ",
+    magnitude: pt_magnitude,
+    currency: pt_currency,
+    minor_unit: pt_minor_unit,
+    unit: pt_unit,
+    titles: &[
+        ("Sra.", "senhora"),
+        ("Sr.", "senhor"),
+        ("Dra.", "doutora"),
+        ("Dr.", "doutor"),
+    ],
+};
+
+// ============================================================================
+// French
+// ============================================================================
+
+const FR_UNITS: [&str; 17] = [
+    "zéro", "un", "deux", "trois", "quatre", "cinq", "six", "sept", "huit", "neuf", "dix",
+    "onze", "douze", "treize", "quatorze", "quinze", "seize",
+];
+
+const FR_TENS: [&str; 7] = ["", "", "vingt", "trente", "quarante", "cinquante", "soixante"];
+
+fn fr_below_100(n: u64) -> String {
+    match n {
+        0..=16 => FR_UNITS[n as usize].to_string(),
+        17..=19 => format!("dix-{}", FR_UNITS[(n - 10) as usize]),
+        20..=69 => {
+            let tens = FR_TENS[(n / 10) as usize];
+            match n % 10 {
+                0 => tens.to_string(),
+                1 => format!("{} et un", tens),
+                unit => format!("{}-{}", tens, FR_UNITS[unit as usize]),
+            }
+        }
+        71 => "soixante et onze".to_string(),
+        70..=79 => format!("soixante-{}", fr_below_100(n - 60)),
+        80 => "quatre-vingts".to_string(),
+        _ => format!("quatre-vingt-{}", fr_below_100(n - 80)),
+    }
+}
+
+fn fr_cardinal(n: u64) -> String {
+    fn with_rest(head: String, rest: u64) -> String {
+        if rest == 0 { head } else { format!("{} {}", head, fr_cardinal(rest)) }
+    }
+
+    fn scale(n: u64, size: u64, one: &str, many: &str) -> String {
+        let count = n / size;
+        let head = if count == 1 {
+            format!("un {}", one)
+        } else {
+            format!("{} {}", fr_cardinal(count), many)
+        };
+        with_rest(head, n % size)
+    }
+
+    match n {
+        0..=99 => fr_below_100(n),
+        100..=999 => {
+            let hundreds = n / 100;
+            let rest = n % 100;
+            let head = match (hundreds, rest) {
+                (1, _) => "cent".to_string(),
+                (_, 0) => format!("{} cents", FR_UNITS[hundreds as usize]),
+                _ => format!("{} cent", FR_UNITS[hundreds as usize]),
+            };
+            with_rest(head, rest)
+        }
+        1_000..=999_999 => {
+            let thousands = n / 1_000;
+            let head = if thousands == 1 {
+                "mille".to_string()
+            } else {
+                // "mille" is invariable and stops the plural of cents/vingts
+                let count = fr_cardinal(thousands);
+                let count = if count.ends_with("cents") || count.ends_with("vingts") {
+                    count[..count.len() - 1].to_string()
+                } else {
+                    count
+                };
+                format!("{} mille", count)
+            };
+            with_rest(head, n % 1_000)
+        }
+        1_000_000..=999_999_999 => scale(n, 1_000_000, "million", "millions"),
+        1_000_000_000..=999_999_999_999 => scale(n, 1_000_000_000, "milliard", "milliards"),
+        _ => scale(n, 1_000_000_000_000, "billion", "billions"),
+    }
+}
+
+fn fr_ordinal(n: u64, feminine: bool) -> String {
+    if n == 1 {
+        return if feminine { "première" } else { "premier" }.to_string();
+    }
+    let mut cardinal = fr_cardinal(n);
+    if cardinal.ends_with("cents") || cardinal.ends_with("vingts") {
+        cardinal.pop();
+    }
+    if let Some(stem) = cardinal.strip_suffix("cinq") {
+        format!("{}cinquième", stem)
+    } else if let Some(stem) = cardinal.strip_suffix("neuf") {
+        format!("{}neuvième", stem)
+    } else if let Some(stem) = cardinal.strip_suffix('e') {
+        format!("{}ième", stem)
+    } else {
+        format!("{}ième", cardinal)
+    }
+}
+
+fn fr_magnitude(mag: Magnitude, plural: bool) -> &'static str {
+    match mag {
+        Magnitude::Thousand => "mille",
+        Magnitude::Million => pick(plural, "million", "millions"),
+        Magnitude::Billion => pick(plural, "milliard", "milliards"),
+        Magnitude::Trillion => pick(plural, "billion", "billions"),
+        Magnitude::Lakh => "lakh",
+        Magnitude::Crore => "crore",
+    }
+}
+
+fn fr_currency(currency: Currency, plural: bool) -> &'static str {
+    match currency {
+        Currency::Dollar => pick(plural, "dollar", "dollars"),
+        Currency::UsDollar => pick(plural, "dollar américain", "dollars américains"),
+        Currency::CanadianDollar => pick(plural, "dollar canadien", "dollars canadiens"),
+        Currency::AustralianDollar => pick(plural, "dollar australien", "dollars australiens"),
+        Currency::NewZealandDollar => pick(plural, "dollar néo-zélandais", "dollars néo-zélandais"),
+        Currency::HongKongDollar => pick(plural, "dollar de Hong Kong", "dollars de Hong Kong"),
+        Currency::SingaporeDollar => pick(plural, "dollar de Singapour", "dollars de Singapour"),
+        Currency::Real => pick(plural, "réal brésilien", "réaux brésiliens"),
+        Currency::Pound => pick(plural, "livre", "livres"),
+        Currency::BritishPound => pick(plural, "livre sterling", "livres sterling"),
+        Currency::Euro => pick(plural, "euro", "euros"),
+        Currency::Rupee => pick(plural, "roupie", "roupies"),
+        Currency::IndianRupee => pick(plural, "roupie indienne", "roupies indiennes"),
+        Currency::Yen => pick(plural, "yen", "yens"),
+        Currency::JapaneseYen => pick(plural, "yen japonais", "yens japonais"),
+        Currency::Yuan => pick(plural, "yuan", "yuans"),
+        Currency::Won => pick(plural, "won", "wons"),
+        Currency::KoreanWon => pick(plural, "won sud-coréen", "wons sud-coréens"),
+        Currency::Riyal => pick(plural, "riyal saoudien", "riyals saoudiens"),
+    }
+}
+
+fn fr_minor_unit(currency: Currency, plural: bool) -> Option<&'static str> {
+    match currency {
+        Currency::Euro => Some(pick(plural, "centime", "centimes")),
+        Currency::Pound | Currency::BritishPound => Some(pick(plural, "penny", "pence")),
+        Currency::Rupee | Currency::IndianRupee => Some("paisas"),
+        Currency::Yen | Currency::JapaneseYen | Currency::Won | Currency::KoreanWon
+        | Currency::Yuan | Currency::Riyal => None,
+        _ => Some(pick(plural, "cent", "cents")),
+    }
+}
+
+fn fr_unit(unit: Unit, plural: bool) -> &'static str {
+    match unit {
+        Unit::Kilometer => pick(plural, "{} kilomètre", "{} kilomètres"),
+        Unit::Meter => pick(plural, "{} mètre", "{} mètres"),
+        Unit::Centimeter => pick(plural, "{} centimètre", "{} centimètres"),
+        Unit::Millimeter => pick(plural, "{} millimètre", "{} millimètres"),
+        Unit::Kilogram => pick(plural, "{} kilogramme", "{} kilogrammes"),
+        Unit::Gram => pick(plural, "{} gramme", "{} grammes"),
+        Unit::Milligram => pick(plural, "{} milligramme", "{} milligrammes"),
+        Unit::PoundWeight => pick(plural, "{} livre", "{} livres"),
+        Unit::Mile => pick(plural, "{} mile", "{} miles"),
+        Unit::Hour => pick(plural, "{} heure", "{} heures"),
+        Unit::KilometerPerHour => pick(plural, "{} kilomètre par heure", "{} kilomètres par heure"),
+        Unit::MilePerHour => pick(plural, "{} mile par heure", "{} miles par heure"),
+        Unit::MeterPerSecond => pick(plural, "{} mètre par seconde", "{} mètres par seconde"),
+        Unit::Celsius => pick(plural, "{} degré Celsius", "{} degrés Celsius"),
+        Unit::Fahrenheit => pick(plural, "{} degré Fahrenheit", "{} degrés Fahrenheit"),
+    }
+}
+
+static FR: LangRules = LangRules {
+    decimal_comma: true,
+    cardinal: fr_cardinal,
+    ordinal: fr_ordinal,
+    point: "virgule",
+    minus: "moins",
+    plus: "plus",
+    percent: "pour cent",
+    range: "à",
+    and: " et ",
+    of: "de ",
+    magnitude: fr_magnitude,
+    currency: fr_currency,
+    minor_unit: fr_minor_unit,
+    unit: fr_unit,
+    titles: &[
+        ("Mme", "Madame"),
+        ("Mlle", "Mademoiselle"),
+        ("M.", "Monsieur"),
+        ("Dr", "Docteur"),
+        ("Pr", "Professeur"),
+    ],
+};
+
+// ============================================================================
+// Korean
+// ============================================================================
+
+const KO_DIGITS: [&str; 10] = ["영", "일", "이", "삼", "사", "오", "육", "칠", "팔", "구"];
+
+/// Sino-Korean reading of 0..9999 without the leading "일" before 십/백/천
+fn ko_below_10000(n: u64) -> String {
+    let mut out = String::new();
+    for (unit, size) in [("천", 1000), ("백", 100), ("십", 10)] {
+        let digit = (n / size) % 10;
+        if digit > 1 {
+            out.push_str(KO_DIGITS[digit as usize]);
+        }
+        if digit > 0 {
+            out.push_str(unit);
+        }
+    }
+    if !n.is_multiple_of(10) {
+        out.push_str(KO_DIGITS[(n % 10) as usize]);
+    }
+    out
+}
+
+fn ko_cardinal(n: u64) -> String {
+    if n == 0 {
+        return KO_DIGITS[0].to_string();
+    }
+    let mut groups = Vec::new();
+    for (unit, size) in [("조", 1_000_000_000_000u64), ("억", 100_000_000), ("만", 10_000), ("", 1)] {
+        let group = (n / size) % 10_000;
+        if group == 0 {
+            continue;
+        }
+        // 10000 is 만, not 일만
+        let digits = if group == 1 && unit == "만" { String::new() } else { ko_below_10000(group) };
+        groups.push(format!("{}{}", digits, unit));
+    }
+    groups.join(" ")
+}
+
+const KO_NATIVE_UNITS: [&str; 10] = ["", "한", "두", "세", "네", "다섯", "여섯", "일곱", "여덟", "아홉"];
+const KO_NATIVE_TENS: [&str; 10] = ["", "열", "스물", "서른", "마흔", "쉰", "예순", "일흔", "여든", "아흔"];
+
+/// "N번째" uses native Korean numbers in their prenominal form
+fn ko_ordinal(n: u64, _feminine: bool) -> String {
+    let counted = match n {
+        1 => "첫".to_string(),
+        20 => "스무".to_string(),
+        2..=99 => format!(
+            "{}{}",
+            KO_NATIVE_TENS[(n / 10) as usize],
+            KO_NATIVE_UNITS[(n % 10) as usize]
+        ),
+        _ => ko_cardinal(n),
+    };
+    format!("{} 번째", counted)
+}
+
+fn ko_magnitude(mag: Magnitude, _plural: bool) -> &'static str {
+    match mag {
+        Magnitude::Thousand => "천",
+        Magnitude::Million => "백만",
+        Magnitude::Billion => "십억",
+        Magnitude::Trillion => "조",
+        Magnitude::Lakh => "십만",
+        Magnitude::Crore => "천만",
+    }
+}
+
+fn ko_currency(currency: Currency, _plural: bool) -> &'static str {
+    match currency {
+        Currency::Dollar => "달러",
+        Currency::UsDollar => "미국 달러",
+        Currency::CanadianDollar => "캐나다 달러",
+        Currency::AustralianDollar => "호주 달러",
+        Currency::NewZealandDollar => "뉴질랜드 달러",
+        Currency::HongKongDollar => "홍콩 달러",
+        Currency::SingaporeDollar => "싱가포르 달러",
+        Currency::Real => "브라질 헤알",
+        Currency::Pound => "파운드",
+        Currency::BritishPound => "영국 파운드",
+        Currency::Euro => "유로",
+        Currency::Rupee => "루피",
+        Currency::IndianRupee => "인도 루피",
+        Currency::Yen => "엔",
+        Currency::JapaneseYen => "일본 엔",
+        Currency::Yuan => "위안",
+        Currency::Won => "원",
+        Currency::KoreanWon => "원",
+        Currency::Riyal => "사우디 리얄",
+    }
+}
+
+fn ko_minor_unit(currency: Currency, _plural: bool) -> Option<&'static str> {
+    match currency {
+        Currency::Pound | Currency::BritishPound => Some("펜스"),
+        Currency::Rupee | Currency::IndianRupee => Some("파이사"),
+        Currency::Yen | Currency::JapaneseYen | Currency::Won | Currency::KoreanWon
+        | Currency::Yuan | Currency::Riyal => None,
+        _ => Some("센트"),
+    }
+}
+
+fn ko_unit(unit: Unit, _plural: bool) -> &'static str {
+    match unit {
+        Unit::Kilometer => "{} 킬로미터",
+        Unit::Meter => "{} 미터",
+        Unit::Centimeter => "{} 센티미터",
+        Unit::Millimeter => "{} 밀리미터",
+        Unit::Kilogram => "{} 킬로그램",
+        Unit::Gram => "{} 그램",
+        Unit::Milligram => "{} 밀리그램",
+        Unit::PoundWeight => "{} 파운드",
+        Unit::Mile => "{} 마일",
+        Unit::Hour => "{} 시간",
+        Unit::KilometerPerHour => "시속 {} 킬로미터",
+        Unit::MilePerHour => "시속 {} 마일",
+        Unit::MeterPerSecond => "초속 {} 미터",
+        Unit::Celsius => "섭씨 {} 도",
+        Unit::Fahrenheit => "화씨 {} 도",
+    }
+}
+
+static KO: LangRules = LangRules {
+    decimal_comma: false,
+    cardinal: ko_cardinal,
+    ordinal: ko_ordinal,
+    point: "점",
+    minus: "마이너스",
+    plus: "플러스",
+    percent: "퍼센트",
+    range: "에서",
+    and: " ",
+    of: "",
+    magnitude: ko_magnitude,
+    currency: ko_currency,
+    minor_unit: ko_minor_unit,
+    unit: ko_unit,
+    titles: &[],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_text;
+
+    fn check(lang: &str, cases: &[(&str, &str)]) {
+        for (input, expected) in cases {
+            assert_eq!(normalize_text(input, lang), *expected, "{} input {:?}", lang, input);
+        }
+    }
+
+    #[test]
+    fn english() {
+        check("en", &[
+            ("$4.5bn", "four point five billion dollars"),
+            ("It costs $10.50", "It costs ten dollars and fifty cents"),
+            ("in 2024", "in twenty twenty four"),
+            ("in 1905", "in nineteen oh five"),
+            ("the 1990s", "the nineteen nineties"),
+            ("1,234 people", "one thousand two hundred thirty four people"),
+            ("-5°C outside", "minus five degrees Celsius outside"),
+            ("10-20°C", "ten to twenty degrees Celsius"),
+            ("10-20%", "ten to twenty percent"),
+            ("It costs $5 more", "It costs five dollars more"),
+            ("$800m deal", "eight hundred million dollars deal"),
+            ("down -$5 today", "down minus five dollars today"),
+            ("dial 000 now", "dial zero zero zero now"),
+            ("Room 112", "Room one hundred twelve"),
+            ("2.000", "two point zero zero zero"),
+            ("call 112.5", "call one hundred twelve point five"),
+            ("in 2024 the", "in twenty twenty four the"),
+            ("2048 bytes", "two thousand forty eight bytes"),
+            ("2048 MB", "two thousand forty eight MB"),
+            ("the 2000s", "the two thousands"),
+            ("the 1900s", "the nineteen hundreds"),
+            ("down -3% today", "down minus three percent today"),
+            ("temperature -7", "temperature minus seven"),
+            ("version 2.0.1", "version 2.0.1"),
+            ("the 21st floor", "the twenty first floor"),
+            ("5km away", "five kilometers away"),
+            ("Dr. Smith and Mrs. Jones", "Doctor Smith and Missus Jones"),
+            ("call 911", "call nine one one"),
+            ("pages 3-5", "pages three to five"),
+            ("1 million people", "one million people"),
+        ]);
+    }
+
+    #[test]
+    fn spanish() {
+        check("es", &[
+            ("21 años", "veintiún años"),
+            ("1 año", "un año"),
+            ("21 mil", "veintiún mil"),
+            ("21 y 22", "veintiuno y veintidós"),
+            ("1 de mayo", "uno de mayo"),
+            ("1.000.000 personas", "un millón de personas"),
+            ("3,5 kg", "tres coma cinco kilogramos"),
+            ("-5°C", "menos cinco grados Celsius"),
+            ("10 %", "diez por ciento"),
+            ("10,50 €", "diez euros con cincuenta céntimos"),
+            ("1 $", "un dólar"),
+            ("21 €", "veintiún euros"),
+            ("21,50 €", "veintiún euros con cincuenta céntimos"),
+            ("1,5 €", "uno coma cinco euros"),
+            ("-5 €", "menos cinco euros"),
+            ("Sr. García", "señor García"),
+            ("versión 2.0.1", "versión 2.0.1"),
+        ]);
+    }
+
+    #[test]
+    fn portuguese() {
+        check("pt", &[
+            ("1.000.000 pessoas", "um milhão de pessoas"),
+            ("2M pessoas", "dois milhões de pessoas"),
+            ("1.500 livros", "mil e quinhentos livros"),
+            ("21 anos", "vinte e um anos"),
+            ("-5°C", "menos cinco graus Celsius"),
+            ("Sra. Silva", "senhora Silva"),
+        ]);
+    }
+
+    #[test]
+    fn french() {
+        check("fr", &[
+            ("1 000 000 habitants", "un million d'habitants"),
+            ("2 000 000 personnes", "deux millions de personnes"),
+            ("71 ans", "soixante et onze ans"),
+            ("-5°C", "moins cinq degrés Celsius"),
+            ("la 1ère fois", "la première fois"),
+            ("Mme Dupont", "Madame Dupont"),
+        ]);
+    }
+
+    #[test]
+    fn korean() {
+        check("ko", &[
+            ("3000원", "삼천 원"),
+            ("5번째", "다섯 번째"),
+            ("-5°C", "마이너스 섭씨 오 도"),
+        ]);
+    }
+
+    #[test]
+    fn unknown_language_is_unchanged() {
+        check("de", &[("21 Jahre", "21 Jahre")]);
+    }
+}
@@ -0,0 +1,432 @@
+// ============================================================================
+// Audio Post-processing - silence trimming, loudness normalization, limiting
+// ============================================================================
+//
+// Works on mono f32 samples as produced by `TextToSpeech::_infer`. Loudness
+// follows ITU-R BS.1770-4 / EBU R128 (K-weighting, 400 ms blocks with 75%
+// overlap, -70 LUFS absolute and -10 LU relative gates).
+
+use std::ops::Range;
+
+/// Target loudness used when none is given (LUFS)
+pub const DEFAULT_TARGET_LUFS: f32 = -18.0;
+/// Default limiter ceiling (dBFS)
+pub const DEFAULT_PEAK_CEILING_DB: f32 = -1.0;
+/// Largest boost loudness normalization applies (dB), so a quiet chunk is
+/// not pumped up into audible noise
+pub const MAX_GAIN_DB: f32 = 12.0;
+/// Audio measured quieter than this (LUFS) is mostly breath and noise and
+/// is left as is
+pub const LOUDNESS_GATE_LUFS: f32 = -50.0;
+
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+const BLOCK_SECONDS: f64 = 0.4;
+const BLOCK_STEP_SECONDS: f64 = 0.1;
+
+fn db_to_gain(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Energy-based silence trimming
+#[derive(Debug, Clone)]
+pub struct TrimOptions {
+    /// Frames quieter than the loudest frame by more than this (dB) are silence
+    pub threshold_db: f32,
+    /// Frames below this level (dBFS) are always silence
+    pub floor_db: f32,
+    /// Analysis frame length (seconds)
+    pub frame: f32,
+    /// Silence kept before the first and after the last voiced frame (seconds)
+    pub padding: f32,
+}
+
+impl Default for TrimOptions {
+    fn default() -> Self {
+        TrimOptions {
+            threshold_db: -40.0,
+            floor_db: -60.0,
+            frame: 0.01,
+            padding: 0.03,
+        }
+    }
+}
+
+/// Range of `samples` between the first and last voiced frame (plus padding).
+/// Returns the full range if nothing rises above the threshold.
+pub fn voiced_range(samples: &[f32], sample_rate: i32, options: &TrimOptions) -> Range<usize> {
+    let frame_len = ((options.frame * sample_rate as f32) as usize).max(1);
+    let levels: Vec<f32> = samples
+        .chunks(frame_len)
+        .map(|frame| {
+            let mean_square = frame.iter().map(|s| s * s).sum::<f32>() / frame.len() as f32;
+            10.0 * mean_square.max(1e-20).log10()
+        })
+        .collect();
+
+    let loudest = levels.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let threshold = (loudest + options.threshold_db).max(options.floor_db);
+    let first = levels.iter().position(|&l| l > threshold);
+    let last = levels.iter().rposition(|&l| l > threshold);
+
+    match (first, last) {
+        (Some(first), Some(last)) => {
+            let padding = (options.padding * sample_rate as f32) as usize;
+            let start = (first * frame_len).saturating_sub(padding);
+            let end = ((last + 1) * frame_len + padding).min(samples.len());
+            start..end
+        }
+        _ => 0..samples.len(),
+    }
+}
+
+/// Second-order IIR section (direct form I)
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [input, self.x[0]];
+        self.y = [output, self.y[0]];
+        output
+    }
+}
+
+/// The two K-weighting stages (high shelf, then high-pass), with
+/// coefficients derived for any sample rate
+fn k_weighting(sample_rate: f64) -> [Biquad; 2] {
+    use std::f64::consts::PI;
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / sample_rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / sample_rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+fn block_loudness(mean_square: f64) -> f64 {
+    -0.691 + 10.0 * mean_square.max(1e-20).log10()
+}
+
+/// Gated integrated loudness in LUFS, or `None` if the audio is shorter than
+/// one 400 ms block or entirely below the absolute gate
+pub fn integrated_loudness(samples: &[f32], sample_rate: i32) -> Option<f32> {
+    let rate = sample_rate as f64;
+    let block_len = (BLOCK_SECONDS * rate) as usize;
+    let step = (BLOCK_STEP_SECONDS * rate) as usize;
+    if block_len == 0 || step == 0 || samples.len() < block_len {
+        return None;
+    }
+
+    let [mut shelf, mut high_pass] = k_weighting(rate);
+    let weighted: Vec<f64> = samples
+        .iter()
+        .map(|&s| high_pass.process(shelf.process(s as f64)))
+        .collect();
+
+    // Prefix sums of squares make every block O(1)
+    let mut energy = Vec::with_capacity(weighted.len() + 1);
+    energy.push(0.0);
+    for s in &weighted {
+        energy.push(energy.last().unwrap() + s * s);
+    }
+    let blocks: Vec<f64> = (0..=(weighted.len() - block_len) / step)
+        .map(|j| (energy[j * step + block_len] - energy[j * step]) / block_len as f64)
+        .collect();
+
+    let gated_mean = |threshold: f64| {
+        let kept: Vec<f64> = blocks
+            .iter()
+            .copied()
+            .filter(|&z| block_loudness(z) > threshold)
+            .collect();
+        (!kept.is_empty()).then(|| kept.iter().sum::<f64>() / kept.len() as f64)
+    };
+
+    let absolute = gated_mean(ABSOLUTE_GATE_LUFS)?;
+    let relative_gate = block_loudness(absolute) + RELATIVE_GATE_LU;
+    let relative = gated_mean(relative_gate)?;
+    Some(block_loudness(relative) as f32)
+}
+
+/// Scale `samples` towards `target_lufs`, boosting by at most `max_gain_db`;
+/// returns the applied gain in dB, or `None` if the loudness could not be
+/// measured or is below `LOUDNESS_GATE_LUFS`
+pub fn normalize_loudness(samples: &mut [f32], sample_rate: i32, target_lufs: f32, max_gain_db: f32) -> Option<f32> {
+    let loudness = integrated_loudness(samples, sample_rate)?;
+    if loudness < LOUDNESS_GATE_LUFS {
+        return None;
+    }
+    let gain_db = (target_lufs - loudness).min(max_gain_db);
+    let gain = db_to_gain(gain_db);
+    for s in samples.iter_mut() {
+        *s *= gain;
+    }
+    Some(gain_db)
+}
+
+/// Look-ahead peak limiter: keeps `|sample| <= ceiling` by ramping the gain
+/// down over `attack` seconds before a peak and back up over `release` seconds
+pub fn limit_peaks(samples: &mut [f32], sample_rate: i32, ceiling_db: f32, attack: f32, release: f32) {
+    let ceiling = db_to_gain(ceiling_db);
+    if samples.iter().all(|s| s.abs() <= ceiling) {
+        return;
+    }
+
+    let mut gain: Vec<f32> = samples
+        .iter()
+        .map(|s| if s.abs() > ceiling { ceiling / s.abs() } else { 1.0 })
+        .collect();
+
+    // Exponential release after each reduction
+    let release_samples = (release * sample_rate as f32).max(1.0);
+    let release_coeff = (-1.0 / release_samples).exp();
+    for i in 1..gain.len() {
+        let recovered = 1.0 - (1.0 - gain[i - 1]) * release_coeff;
+        gain[i] = gain[i].min(recovered);
+    }
+
+    // Linear attack ahead of each reduction (the look-ahead)
+    let attack_step = 1.0 / (attack * sample_rate as f32).max(1.0);
+    for i in (0..gain.len().saturating_sub(1)).rev() {
+        gain[i] = gain[i].min(gain[i + 1] + attack_step);
+    }
+
+    for (s, g) in samples.iter_mut().zip(&gain) {
+        *s *= g;
+    }
+}
+
+/// Post-processing chain: trim, then loudness normalization, then limiting.
+/// Every stage is optional; the default does nothing.
+#[derive(Debug, Clone, Default)]
+pub struct PostProcess {
+    pub trim: Option<TrimOptions>,
+    pub target_lufs: Option<f32>,
+    pub peak_ceiling_db: Option<f32>,
+}
+
+/// What `PostProcess::apply` did, so callers can keep timings in sync
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PostProcessReport {
+    /// Samples removed from the start
+    pub trimmed_start: usize,
+    /// Samples removed from the end
+    pub trimmed_end: usize,
+    /// Loudness gain applied (dB)
+    pub gain_db: f32,
+    /// Whether `gain_db` came from measuring this audio rather than from the
+    /// fallback gain
+    pub measured: bool,
+}
+
+impl PostProcess {
+    /// Trim, normalize to `DEFAULT_TARGET_LUFS` and limit to `DEFAULT_PEAK_CEILING_DB`
+    pub fn standard() -> Self {
+        PostProcess {
+            trim: Some(TrimOptions::default()),
+            target_lufs: Some(DEFAULT_TARGET_LUFS),
+            peak_ceiling_db: Some(DEFAULT_PEAK_CEILING_DB),
+        }
+    }
+
+    pub fn is_noop(&self) -> bool {
+        self.trim.is_none() && self.target_lufs.is_none() && self.peak_ceiling_db.is_none()
+    }
+
+    pub fn apply(&self, samples: &mut Vec<f32>, sample_rate: i32) -> PostProcessReport {
+        self.apply_with_fallback_gain(samples, sample_rate, None)
+    }
+
+    /// Like `apply`, but audio too short to measure (under one 400 ms block)
+    /// gets `fallback_gain_db` instead of keeping its original level, so a
+    /// short chunk in a stream matches the loudness of the chunks around it
+    pub fn apply_with_fallback_gain(
+        &self,
+        samples: &mut Vec<f32>,
+        sample_rate: i32,
+        fallback_gain_db: Option<f32>,
+    ) -> PostProcessReport {
+        let mut report = PostProcessReport::default();
+
+        if let Some(trim) = &self.trim {
+            let range = voiced_range(samples, sample_rate, trim);
+            report.trimmed_start = range.start;
+            report.trimmed_end = samples.len() - range.end;
+            samples.truncate(range.end);
+            samples.drain(..range.start);
+        }
+        if let Some(target) = self.target_lufs {
+            let too_short = samples.len() < (BLOCK_SECONDS * sample_rate as f64) as usize;
+            match normalize_loudness(samples, sample_rate, target, MAX_GAIN_DB) {
+                Some(gain_db) => {
+                    report.gain_db = gain_db;
+                    report.measured = true;
+                }
+                None => {
+                    if let Some(gain_db) = fallback_gain_db.filter(|_| too_short) {
+                        let gain_db = gain_db.min(MAX_GAIN_DB);
+                        let gain = db_to_gain(gain_db);
+                        for s in samples.iter_mut() {
+                            *s *= gain;
+                        }
+                        report.gain_db = gain_db;
+                    }
+                }
+            }
+        }
+        if let Some(ceiling) = self.peak_ceiling_db {
+            limit_peaks(samples, sample_rate, ceiling, 0.005, 0.05);
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: i32 = 16000;
+
+    fn sine(frequency: f32, amplitude: f32, seconds: f32) -> Vec<f32> {
+        let len = (seconds * RATE as f32) as usize;
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * frequency * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_1k_sine_measures_near_minus_three_lufs() {
+        // BS.1770 reference: a 0 dBFS 997 Hz sine reads -3.01 LUFS
+        let loudness = integrated_loudness(&sine(997.0, 1.0, 2.0), RATE).unwrap();
+        assert!((loudness + 3.01).abs() < 0.1, "{}", loudness);
+
+        // Every halving of the amplitude drops it by 6 dB
+        let quieter = integrated_loudness(&sine(997.0, 0.5, 2.0), RATE).unwrap();
+        assert!((loudness - quieter - 6.02).abs() < 0.05, "{} {}", loudness, quieter);
+    }
+
+    #[test]
+    fn loudness_needs_one_block_and_a_signal() {
+        assert_eq!(integrated_loudness(&sine(997.0, 1.0, 0.39), RATE), None);
+        assert_eq!(integrated_loudness(&vec![0.0; RATE as usize], RATE), None);
+    }
+
+    #[test]
+    fn normalization_reaches_the_target_and_caps_the_boost() {
+        let mut samples = sine(997.0, 0.1, 2.0);
+        let gain_db = normalize_loudness(&mut samples, RATE, -18.0, MAX_GAIN_DB).unwrap();
+        assert!((gain_db - 5.0).abs() < 0.1, "{}", gain_db);
+        let loudness = integrated_loudness(&samples, RATE).unwrap();
+        assert!((loudness + 18.0).abs() < 0.05, "{}", loudness);
+
+        // -43 LUFS would need 25 dB; only MAX_GAIN_DB is applied
+        let mut quiet = sine(997.0, 0.01, 2.0);
+        assert_eq!(normalize_loudness(&mut quiet, RATE, -18.0, MAX_GAIN_DB), Some(MAX_GAIN_DB));
+        let peak = quiet.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.01 * db_to_gain(MAX_GAIN_DB)).abs() < 1e-4, "{}", peak);
+
+        // Below the loudness gate nothing changes
+        let mut noise = sine(997.0, 0.001, 2.0);
+        let before = noise.clone();
+        assert_eq!(normalize_loudness(&mut noise, RATE, -18.0, MAX_GAIN_DB), None);
+        assert_eq!(noise, before);
+    }
+
+    #[test]
+    fn limiter_keeps_peaks_under_the_ceiling() {
+        let mut samples = sine(220.0, 0.3, 1.0);
+        let burst = RATE as usize / 2;
+        for s in &mut samples[burst..burst + 400] {
+            *s *= 4.0;
+        }
+        limit_peaks(&mut samples, RATE, -1.0, 0.005, 0.05);
+
+        let ceiling = db_to_gain(-1.0);
+        assert!(samples.iter().all(|s| s.abs() <= ceiling + 1e-6));
+        // Audio well before the burst (beyond the attack) is untouched
+        let untouched = sine(220.0, 0.3, 1.0);
+        assert_eq!(samples[..burst - 200], untouched[..burst - 200]);
+
+        // Audio already under the ceiling passes through
+        let mut quiet = sine(220.0, 0.5, 0.1);
+        let before = quiet.clone();
+        limit_peaks(&mut quiet, RATE, -1.0, 0.005, 0.05);
+        assert_eq!(quiet, before);
+    }
+
+    #[test]
+    fn trimming_keeps_the_voiced_part_plus_padding() {
+        let options = TrimOptions::default();
+        let mut samples = vec![0.0; 8000];
+        samples.extend(sine(220.0, 0.5, 1.0));
+        samples.extend(vec![0.0; 4000]);
+
+        // 160-sample frames, 480 samples of padding on each side
+        let range = voiced_range(&samples, RATE, &options);
+        assert_eq!(range, 8000 - 480..8000 + 16000 + 480);
+
+        // Padding never runs past either end
+        let voiced = sine(220.0, 0.5, 0.5);
+        assert_eq!(voiced_range(&voiced, RATE, &options), 0..voiced.len());
+        // Pure silence is left whole
+        assert_eq!(voiced_range(&[0.0; 1000], RATE, &options), 0..1000);
+    }
+
+    #[test]
+    fn short_chunks_take_the_fallback_gain() {
+        let chain = PostProcess {
+            trim: None,
+            target_lufs: Some(-18.0),
+            peak_ceiling_db: None,
+        };
+
+        let mut short = sine(997.0, 0.1, 0.2);
+        let report = chain.apply(&mut short, RATE);
+        assert_eq!((report.gain_db, report.measured), (0.0, false));
+
+        let report = chain.apply_with_fallback_gain(&mut short, RATE, Some(6.0));
+        assert_eq!((report.gain_db, report.measured), (6.0, false));
+        let peak = short.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.1 * db_to_gain(6.0)).abs() < 1e-3, "{}", peak);
+
+        // Measurable audio ignores the fallback
+        let mut long = sine(997.0, 0.1, 2.0);
+        let report = chain.apply_with_fallback_gain(&mut long, RATE, Some(6.0));
+        assert!(report.measured);
+        assert!((report.gain_db - 5.0).abs() < 0.1, "{}", report.gain_db);
+    }
+}
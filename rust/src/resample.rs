@@ -0,0 +1,299 @@
+// ============================================================================
+// Sample Rate Conversion - windowed-sinc polyphase resampler
+// ============================================================================
+//
+// The ratio is reduced to `up / down`; output sample `n` sits at input time
+// `n * down / up`. Each output is a dot product of the surrounding input with
+// one row of a precomputed Kaiser-windowed sinc table. Ratios with more than
+// `MAX_PHASES` phases interpolate between neighbouring rows.
+
+use anyhow::Result;
+
+/// Highest supported input or output rate (Hz)
+pub const MAX_SAMPLE_RATE: u32 = 384_000;
+
+/// Zero crossings of the sinc on each side of the centre
+const ZERO_CROSSINGS: f64 = 16.0;
+/// Passband edge relative to the lower of the two Nyquist frequencies
+const ROLLOFF: f64 = 0.945;
+/// Kaiser window shape (about 90 dB stopband attenuation)
+const KAISER_BETA: f64 = 9.0;
+const MAX_PHASES: u64 = 1024;
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Zeroth-order modified Bessel function of the first kind
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let half = x / 2.0;
+    for k in 1..64 {
+        term *= half / k as f64;
+        sum += term * term;
+        if term * term < sum * 1e-17 {
+            break;
+        }
+    }
+    sum
+}
+
+/// Streaming resampler for mono f32 audio.
+///
+/// Feed input with `process` as it arrives and call `flush` after the last
+/// block; the concatenated outputs are identical to resampling everything at
+/// once and have `ceil(input_len * to / from)` samples. Output is held back by
+/// half the filter length until the input after it is known.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    from: u32,
+    to: u32,
+    up: u64,
+    down: u64,
+    phases: u64,
+    taps: usize,
+    /// `phases + 1` rows of `taps` coefficients
+    table: Vec<f32>,
+    /// Input from absolute index `history_start` on (negative = leading zeros)
+    history: Vec<f32>,
+    history_start: i64,
+    consumed: u64,
+    produced: u64,
+}
+
+impl Resampler {
+    pub fn new(from: u32, to: u32) -> Result<Self> {
+        for (name, rate) in [("input", from), ("output", to)] {
+            if rate == 0 || rate > MAX_SAMPLE_RATE {
+                anyhow::bail!("Unsupported {} sample rate: {} Hz (1..={})", name, rate, MAX_SAMPLE_RATE);
+            }
+        }
+        let divisor = gcd(from as u64, to as u64);
+        let (up, down) = (to as u64 / divisor, from as u64 / divisor);
+        let phases = up.min(MAX_PHASES);
+
+        // Cutoff in cycles per input sample, scaled so the kernel sums to 1
+        let cutoff = (to as f64 / from as f64).min(1.0) * ROLLOFF;
+        let half = (ZERO_CROSSINGS / cutoff).ceil() as usize;
+        let taps = 2 * half;
+        let kernel = |x: f64| {
+            let u = x / half as f64;
+            if u.abs() >= 1.0 {
+                return 0.0;
+            }
+            let arg = std::f64::consts::PI * cutoff * x;
+            let sinc = if arg.abs() < 1e-12 { 1.0 } else { arg.sin() / arg };
+            cutoff * sinc * bessel_i0(KAISER_BETA * (1.0 - u * u).sqrt()) / bessel_i0(KAISER_BETA)
+        };
+
+        let mut table = Vec::with_capacity((phases as usize + 1) * taps);
+        for p in 0..=phases {
+            let phase = p as f64 / phases as f64;
+            let row: Vec<f64> = (0..taps)
+                .map(|j| kernel(phase - (j as f64 - (half as f64 - 1.0))))
+                .collect();
+            // Unity gain at DC for every phase
+            let sum: f64 = row.iter().sum();
+            table.extend(row.iter().map(|&c| (c / sum) as f32));
+        }
+
+        let mut resampler = Resampler {
+            from,
+            to,
+            up,
+            down,
+            phases,
+            taps,
+            table,
+            history: Vec::new(),
+            history_start: 0,
+            consumed: 0,
+            produced: 0,
+        };
+        resampler.reset();
+        Ok(resampler)
+    }
+
+    pub fn input_rate(&self) -> u32 {
+        self.from
+    }
+
+    pub fn output_rate(&self) -> u32 {
+        self.to
+    }
+
+    fn is_passthrough(&self) -> bool {
+        self.up == self.down
+    }
+
+    /// Input samples fed since the last reset
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Output samples returned since the last reset
+    pub fn produced(&self) -> u64 {
+        self.produced
+    }
+
+    /// Index of the first output sample at or after input sample `input_pos`
+    pub fn output_position(&self, input_pos: u64) -> u64 {
+        (input_pos * self.up).div_ceil(self.down)
+    }
+
+    /// Forget all buffered input and start a new signal
+    pub fn reset(&mut self) {
+        let half = self.taps / 2;
+        self.history = vec![0.0; half - 1];
+        self.history_start = -(half as i64 - 1);
+        self.consumed = 0;
+        self.produced = 0;
+    }
+
+    /// Resample the next block of input
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() {
+            self.consumed += input.len() as u64;
+            self.produced += input.len() as u64;
+            return input.to_vec();
+        }
+        self.history.extend_from_slice(input);
+        self.consumed += input.len() as u64;
+        self.drain()
+    }
+
+    /// Emit the remaining output (the input is taken to be followed by
+    /// silence) and reset for the next signal
+    pub fn flush(&mut self) -> Vec<f32> {
+        if self.is_passthrough() {
+            self.reset();
+            return Vec::new();
+        }
+        self.history.resize(self.history.len() + self.taps / 2, 0.0);
+        let output = self.drain();
+        self.reset();
+        output
+    }
+
+    /// Produce every output whose filter window is inside the history
+    fn drain(&mut self) -> Vec<f32> {
+        let half = (self.taps / 2) as i64;
+        let limit = self.history_start + self.history.len() as i64;
+        let target = self.output_position(self.consumed);
+
+        let mut output = Vec::new();
+        while self.produced < target {
+            let position = self.produced * self.down;
+            let ipos = (position / self.up) as i64;
+            if ipos + half >= limit {
+                break;
+            }
+            let frac = position % self.up;
+
+            let start = (ipos - (half - 1) - self.history_start) as usize;
+            let window = &self.history[start..start + self.taps];
+            let scaled = frac * self.phases;
+            let (row, rem) = ((scaled / self.up) as usize, scaled % self.up);
+            let mut value = dot(window, &self.table[row * self.taps..(row + 1) * self.taps]);
+            if rem != 0 {
+                let next = dot(window, &self.table[(row + 1) * self.taps..(row + 2) * self.taps]);
+                value += (next - value) * (rem as f32 / self.up as f32);
+            }
+            output.push(value);
+            self.produced += 1;
+        }
+
+        // Drop input no later output can reach
+        let next_ipos = (self.produced * self.down / self.up) as i64;
+        let keep_from = next_ipos - (half - 1);
+        if keep_from > self.history_start {
+            let drop = ((keep_from - self.history_start) as usize).min(self.history.len());
+            self.history.drain(..drop);
+            self.history_start += drop as i64;
+        }
+        output
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Resample a complete signal
+pub fn resample(samples: &[f32], from: u32, to: u32) -> Result<Vec<f32>> {
+    if from == to {
+        return Ok(samples.to_vec());
+    }
+    let mut resampler = Resampler::new(from, to)?;
+    let mut output = resampler.process(samples);
+    output.extend(resampler.flush());
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(len: usize, freq: f32, sample_rate: u32) -> Vec<f32> {
+        (0..len)
+            .map(|i| 0.5 * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    #[test]
+    fn up_and_down_round_trip() {
+        let input = sine(4800, 440.0, 24_000);
+        for rate in [44_100, 48_000] {
+            let up = resample(&input, 24_000, rate).unwrap();
+            let back = resample(&up, rate, 24_000).unwrap();
+            assert_eq!(back.len(), input.len());
+            // The filter settles after its half length; compare the middle
+            let max_error = input[200..4600]
+                .iter()
+                .zip(&back[200..4600])
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f32::max);
+            assert!(max_error < 1e-3, "{} Hz: max error {}", rate, max_error);
+        }
+    }
+
+    #[test]
+    fn streaming_matches_one_shot() {
+        let input = sine(10_000, 1000.0, 44_100);
+        for (from, to) in [(44_100, 24_000), (24_000, 44_100), (24_000, 16_000), (44_100, 44_101)] {
+            let one_shot = resample(&input, from, to).unwrap();
+
+            // Uneven block sizes, including empty and single-sample blocks
+            let mut resampler = Resampler::new(from, to).unwrap();
+            let mut streamed = Vec::new();
+            let mut pos = 0;
+            for block in [0, 1, 7, 333, 0, 1024, 1, 4096].iter().cycle() {
+                let end = (pos + block).min(input.len());
+                streamed.extend(resampler.process(&input[pos..end]));
+                pos = end;
+                if pos == input.len() {
+                    break;
+                }
+            }
+            streamed.extend(resampler.flush());
+
+            assert_eq!(streamed, one_shot, "{} -> {} Hz", from, to);
+        }
+    }
+
+    #[test]
+    fn output_length_is_ceil_of_ratio() {
+        for (from, to) in [(24_000u32, 48_000u32), (44_100, 24_000), (24_000, 22_050), (48_000, 8_000), (16_000, 44_100)] {
+            for n in [0usize, 1, 2, 99, 100, 1001, 4801] {
+                let input = vec![0.25; n];
+                let output = resample(&input, from, to).unwrap();
+                let expected = (n as u64 * to as u64).div_ceil(from as u64) as usize;
+                assert_eq!(output.len(), expected, "{} samples, {} -> {} Hz", n, from, to);
+            }
+        }
+    }
+}
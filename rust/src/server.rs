@@ -0,0 +1,534 @@
+// ============================================================================
+// Local HTTP synthesis server
+// ============================================================================
+//
+// Loads the models once per worker and serves:
+//
+//   GET  /health      {"status": "ok", ...}
+//   GET  /voices      the voice library as `VoiceRegistry::to_json`
+//   POST /synthesize  JSON request, audio file or streamed 16-bit PCM reply
+//
+// and, on `--ws-port`, a WebSocket endpoint that streams each chunk with its
+// timing as soon as it is synthesized (see `websocket.rs`).
+//
+// Requests wait in a bounded queue; when it is full the server answers 503
+// instead of piling up work. Requests carrying the native host's `command`
+// field get the host's JSON reply (base64 PCM) so existing clients keep working.
+
+use anyhow::Result;
+use base64::Engine as _;
+use clap::Parser;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::net::TcpListener;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+mod websocket;
+
+use supertonic_tts::encoder::{encode_audio, to_pcm16_dithered, AudioEncoding, Dither};
+use supertonic_tts::helper::{
+    is_valid_lang, load_text_to_speech_with_options, load_voice_style, EngineOptions, Style, SynthesisOptions,
+    TextToSpeech,
+};
+use supertonic_tts::lexicon::Lexicon;
+use supertonic_tts::resample::Resampler;
+use supertonic_tts::ssml::is_ssml;
+use supertonic_tts::voices::VoiceRegistry;
+use websocket::StreamJob;
+
+/// Largest accepted request body
+const MAX_BODY_BYTES: usize = 1 << 20;
+/// Streamed PCM chunks buffered ahead of a slow client
+const STREAM_BUFFER_CHUNKS: usize = 16;
+
+#[derive(Parser, Debug)]
+#[command(name = "tts_server")]
+#[command(about = "Local HTTP TTS server", long_about = None)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    /// Port to listen on
+    #[arg(long, default_value = "8080")]
+    port: u16,
+
+    /// Port for streaming over WebSocket (0 disables it)
+    #[arg(long, default_value = "8081")]
+    ws_port: u16,
+
+    /// Path to ONNX model directory
+    #[arg(long, default_value = "assets/onnx")]
+    onnx_dir: String,
+
+    /// Directory of voice styles (and optional metadata)
+    #[arg(long, default_value = "assets/voice_styles")]
+    voice_dir: String,
+
+    /// Use GPU for inference (default: CPU)
+    #[arg(long, default_value = "false")]
+    use_gpu: bool,
+
+    /// Requests synthesized concurrently (each worker loads its own models and
+    /// runs them on an equal share of the CPU cores)
+    #[arg(long, default_value = "1")]
+    workers: usize,
+
+    /// Pronunciation lexicon (JSON array of {term, replacement, ...})
+    #[arg(long)]
+    lexicon: Option<String>,
+
+    /// Requests allowed to wait for a worker before new ones get 503
+    #[arg(long, default_value = "8")]
+    queue: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct SynthesizeRequest {
+    text: String,
+    #[serde(default = "default_lang")]
+    lang: String,
+    /// Voice id from the voice directory
+    #[serde(default)]
+    voice: Option<String>,
+    /// Style file name, as sent by the browser extension; resolved inside the
+    /// voice directory only
+    #[serde(default)]
+    voice_style_path: Option<String>,
+    #[serde(default)]
+    speed: Option<f32>,
+    #[serde(default)]
+    total_step: Option<usize>,
+    #[serde(default)]
+    seed: Option<u64>,
+    /// Output sample rate (Hz); the model's rate by default
+    #[serde(default)]
+    sample_rate: Option<u32>,
+    /// `pcm` for streamed 16-bit PCM, or any `AudioEncoding` name (default `wav`)
+    #[serde(default)]
+    format: Option<String>,
+    /// Set by native host clients; selects the host's JSON reply
+    #[serde(default)]
+    command: Option<String>,
+}
+
+fn default_lang() -> String {
+    "en".to_string()
+}
+
+/// Work waiting for a synthesis worker
+enum Job {
+    Http(Request),
+    Stream(StreamJob),
+}
+
+enum ReplyFormat {
+    /// `{"status": "success", "audio": <base64 PCM>, "sample_rate": n}`
+    HostJson,
+    /// Raw 16-bit little-endian PCM, sent chunk by chunk as it is synthesized
+    Pcm,
+    File(AudioEncoding),
+}
+
+/// A request that failed before any audio was sent
+struct HttpError {
+    status: u16,
+    message: String,
+}
+
+impl HttpError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        HttpError { status, message: message.into() }
+    }
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("valid header")
+}
+
+fn cors_headers() -> Vec<Header> {
+    vec![
+        header("Access-Control-Allow-Origin", "*"),
+        header("Access-Control-Allow-Methods", "GET, POST, OPTIONS"),
+        header("Access-Control-Allow-Headers", "Content-Type"),
+    ]
+}
+
+fn json_response(status: u16, value: &serde_json::Value) -> Response<Cursor<Vec<u8>>> {
+    let mut response = Response::from_data(value.to_string().into_bytes())
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"));
+    for h in cors_headers() {
+        response.add_header(h);
+    }
+    response
+}
+
+fn error_response(error: &HttpError) -> Response<Cursor<Vec<u8>>> {
+    json_response(error.status, &serde_json::json!({ "error": error.message }))
+}
+
+fn respond(request: Request, response: Response<Cursor<Vec<u8>>>) {
+    if let Err(e) = request.respond(response) {
+        eprintln!("Failed to send response: {}", e);
+    }
+}
+
+/// Response body fed by a synthesis worker; ends when the sender is dropped
+struct ChannelReader {
+    receiver: Receiver<Vec<u8>>,
+    current: Cursor<Vec<u8>>,
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let n = self.current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            match self.receiver.recv() {
+                Ok(chunk) => self.current = Cursor::new(chunk),
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+/// One model instance plus the styles it has loaded
+struct Worker {
+    tts: TextToSpeech,
+    voices: Arc<VoiceRegistry>,
+    styles: HashMap<String, Style>,
+}
+
+impl Worker {
+    fn style(&mut self, id: &str) -> Result<Style, HttpError> {
+        let voice = self
+            .voices
+            .get(id)
+            .ok_or_else(|| HttpError::new(404, format!("Unknown voice '{}'", id)))?;
+        if let Some(style) = self.styles.get(&voice.id) {
+            return Ok(style.clone());
+        }
+        let style = load_voice_style(&[voice.path.to_string_lossy().into_owned()], false)
+            .map_err(|e| HttpError::new(500, format!("Failed to load voice '{}': {:#}", voice.id, e)))?;
+        self.tts
+            .validate_style(&style)
+            .map_err(|e| HttpError::new(500, format!("{:#}", e)))?;
+        self.styles.insert(voice.id.clone(), style.clone());
+        Ok(style)
+    }
+
+    fn handle(&mut self, mut request: Request) {
+        let mut body = Vec::new();
+        let read = request
+            .as_reader()
+            .take(MAX_BODY_BYTES as u64 + 1)
+            .read_to_end(&mut body);
+        let parsed = match read {
+            Err(e) => Err(HttpError::new(400, format!("Failed to read request body: {}", e))),
+            Ok(_) if body.len() > MAX_BODY_BYTES => Err(HttpError::new(413, "Request body too large")),
+            Ok(_) => serde_json::from_slice::<SynthesizeRequest>(&body)
+                .map_err(|e| HttpError::new(400, format!("Invalid request: {}", e))),
+        };
+        let parsed = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => return respond(request, error_response(&e)),
+        };
+
+        self.synthesize(request, parsed);
+    }
+
+    fn prepare(&mut self, req: &SynthesizeRequest) -> Result<(ReplyFormat, Style, SynthesisOptions), HttpError> {
+        if req.text.trim().is_empty() {
+            return Err(HttpError::new(400, "Text is empty"));
+        }
+        if !is_valid_lang(&req.lang) {
+            return Err(HttpError::new(400, format!("Unsupported language '{}'", req.lang)));
+        }
+        let format = match (&req.command, req.format.as_deref()) {
+            (Some(command), _) if command != "synthesize" => {
+                return Err(HttpError::new(400, format!("Unknown command '{}'", command)));
+            }
+            (Some(_), _) => ReplyFormat::HostJson,
+            (None, Some("pcm")) => ReplyFormat::Pcm,
+            (None, Some(name)) => ReplyFormat::File(
+                name.parse().map_err(|e: anyhow::Error| HttpError::new(400, e.to_string()))?,
+            ),
+            (None, None) => ReplyFormat::File(AudioEncoding::Wav16),
+        };
+
+        // Style paths are only used for their file name, so clients cannot
+        // make the server read arbitrary files
+        let voice_id = match (&req.voice, &req.voice_style_path) {
+            (Some(id), _) => id.clone(),
+            (None, Some(path)) => path.rsplit(['/', '\\']).next().unwrap_or(path).to_string(),
+            (None, None) => self
+                .voices
+                .default_voice()
+                .map(|v| v.id.clone())
+                .ok_or_else(|| HttpError::new(500, "No voices available"))?,
+        };
+        let style = self.style(&voice_id)?;
+
+        let mut options = SynthesisOptions {
+            seed: req.seed,
+            output_sample_rate: req.sample_rate,
+            ..Default::default()
+        };
+        if let Some(speed) = req.speed {
+            if !(speed.is_finite() && speed > 0.0) {
+                return Err(HttpError::new(400, format!("Invalid speed {}", speed)));
+            }
+            options.speed = speed;
+        }
+        if let Some(steps) = req.total_step {
+            if !(1..=100).contains(&steps) {
+                return Err(HttpError::new(400, format!("total_step must be 1..=100, got {}", steps)));
+            }
+            options.total_step = steps;
+        }
+        if let Some(rate) = req.sample_rate {
+            Resampler::new(self.tts.sample_rate as u32, rate)
+                .map_err(|e| HttpError::new(400, e.to_string()))?;
+        }
+        Ok((format, style, options))
+    }
+
+    /// Synthesize and reply; errors get `{"error": message}`, which is also
+    /// what native host clients expect
+    fn synthesize(&mut self, request: Request, req: SynthesizeRequest) {
+        let (format, style, options) = match self.prepare(&req) {
+            Ok(prepared) => prepared,
+            Err(e) => return respond(request, error_response(&e)),
+        };
+        let sample_rate = self.tts.output_sample_rate(&options);
+
+        if let ReplyFormat::Pcm = format {
+            if !is_ssml(&req.text) {
+                return self.stream_pcm(request, &req, &style, &options, sample_rate);
+            }
+        }
+
+        let wav = match self.synthesize_all(&req, &style, &options, |_, _, _| true) {
+            Ok(wav) => wav,
+            Err(e) => {
+                return respond(request, error_response(&HttpError::new(500, format!("Synthesis failed: {:#}", e))));
+            }
+        };
+
+        let response = match format {
+            ReplyFormat::HostJson => {
+                let audio = base64::engine::general_purpose::STANDARD.encode(to_pcm16_dithered(&wav));
+                json_response(
+                    200,
+                    &serde_json::json!({ "status": "success", "audio": audio, "sample_rate": sample_rate }),
+                )
+            }
+            ReplyFormat::Pcm => pcm_response(to_pcm16_dithered(&wav), sample_rate),
+            ReplyFormat::File(encoding) => match encode_audio(&wav, sample_rate as u32, encoding) {
+                Ok(bytes) => {
+                    let mut response = Response::from_data(bytes)
+                        .with_header(header("Content-Type", encoding.mime_type()));
+                    for h in cors_headers() {
+                        response.add_header(h);
+                    }
+                    response
+                }
+                Err(e) => return respond(request, error_response(&HttpError::new(400, format!("{:#}", e)))),
+            },
+        };
+        respond(request, response);
+    }
+
+    /// Synthesize the whole request; `progress` is called between chunks as
+    /// in `TextToSpeech::call` and stops synthesis by returning false
+    fn synthesize_all<F>(&mut self, req: &SynthesizeRequest, style: &Style, options: &SynthesisOptions, progress: F) -> Result<Vec<f32>>
+    where
+        F: FnMut(usize, usize, Option<&[f32]>) -> bool,
+    {
+        if is_ssml(&req.text) {
+            let voices = Arc::clone(&self.voices);
+            let resolve_voice = |name: &str| {
+                let voice = voices
+                    .get(name)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown voice '{}'", name))?;
+                load_voice_style(&[voice.path.to_string_lossy().into_owned()], false)
+            };
+            let (wav, _) = self.tts.call_ssml(&req.text, &req.lang, style, options, resolve_voice, progress)?;
+            Ok(wav)
+        } else {
+            let (wav, _, _) = self.tts.call_with_timing(&req.text, &req.lang, style, options, progress)?;
+            Ok(wav)
+        }
+    }
+
+    /// Send every chunk as soon as it is synthesized. A client that goes away
+    /// closes the channel, which stops synthesis after the current chunk.
+    fn stream_pcm(&mut self, request: Request, req: &SynthesizeRequest, style: &Style, options: &SynthesisOptions, sample_rate: i32) {
+        let (sender, receiver): (SyncSender<Vec<u8>>, _) = mpsc::sync_channel(STREAM_BUFFER_CHUNKS);
+        let body = ChannelReader { receiver, current: Cursor::new(Vec::new()) };
+        let mut response = Response::new(
+            StatusCode(200),
+            vec![
+                header("Content-Type", "application/octet-stream"),
+                header("X-Sample-Rate", &sample_rate.to_string()),
+                header("X-Sample-Format", "s16le"),
+                header("X-Channels", "1"),
+            ],
+            body,
+            None,
+            None,
+        );
+        for h in cors_headers() {
+            response.add_header(h);
+        }
+        let writer = thread::spawn(move || {
+            if let Err(e) = request.respond(response) {
+                eprintln!("Client disconnected: {}", e);
+            }
+        });
+
+        let mut dither = Dither::new();
+        for frame in self.tts.stream(&req.text, &req.lang, style, options) {
+            match frame {
+                Ok(frame) => {
+                    if sender.send(dither.pcm16(&frame.samples)).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    // Headers are already out; all we can do is end the stream early
+                    eprintln!("Synthesis failed mid-stream: {:#}", e);
+                    break;
+                }
+            }
+        }
+        drop(sender);
+        let _ = writer.join();
+    }
+}
+
+fn pcm_response(pcm: Vec<u8>, sample_rate: i32) -> Response<Cursor<Vec<u8>>> {
+    let mut response = Response::from_data(pcm)
+        .with_header(header("Content-Type", "application/octet-stream"))
+        .with_header(header("X-Sample-Rate", &sample_rate.to_string()))
+        .with_header(header("X-Sample-Format", "s16le"))
+        .with_header(header("X-Channels", "1"));
+    for h in cors_headers() {
+        response.add_header(h);
+    }
+    response
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let workers = args.workers.max(1);
+    if args.queue == 0 {
+        // A zero-capacity channel only accepts a job while a worker is
+        // already waiting, so nearly every request would get 503
+        anyhow::bail!("--queue must be at least 1");
+    }
+
+    let voices = Arc::new(VoiceRegistry::scan(&args.voice_dir)?);
+    println!("Loaded {} voices from {}", voices.voices().len(), args.voice_dir);
+
+    let (job_sender, job_receiver) = mpsc::sync_channel::<Job>(args.queue);
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+    // Split the cores between the workers instead of letting every worker's
+    // ONNX Runtime sessions claim all of them
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut engine_options = EngineOptions::default();
+    engine_options.session.intra_threads = Some((cores / workers).max(1));
+
+    let mut sample_rate = 0;
+    for i in 0..workers {
+        let mut tts = load_text_to_speech_with_options(&args.onnx_dir, args.use_gpu, &engine_options)?;
+        if let Some(path) = &args.lexicon {
+            let lexicon = Lexicon::load(path)?;
+            if i == 0 {
+                println!("Loaded {} lexicon entries from {} ({} invalid skipped)", lexicon.len(), path, lexicon.skipped());
+            }
+            tts.set_lexicon(lexicon);
+        }
+        sample_rate = tts.sample_rate;
+        let mut worker = Worker { tts, voices: Arc::clone(&voices), styles: HashMap::new() };
+        let jobs = Arc::clone(&job_receiver);
+        thread::Builder::new()
+            .name(format!("tts-worker-{}", i))
+            .spawn(move || loop {
+                // The lock is only held while waiting, not while synthesizing
+                let job = match jobs.lock().unwrap_or_else(PoisonError::into_inner).recv() {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                // A panic fails this request only; the worker keeps serving
+                let handled = panic::catch_unwind(AssertUnwindSafe(|| match job {
+                    Job::Http(request) => worker.handle(request),
+                    Job::Stream(job) => worker.handle_stream(job),
+                }));
+                if handled.is_err() {
+                    eprintln!("Worker {} panicked while handling a request", i);
+                }
+            })?;
+    }
+
+    let address = format!("{}:{}", args.host, args.port);
+    let server = Server::http(&address).map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", address, e))?;
+    println!("Serving on http://{} ({} workers, queue {})", address, workers, args.queue);
+
+    if args.ws_port != 0 {
+        let ws_address = format!("{}:{}", args.host, args.ws_port);
+        let listener = TcpListener::bind(&ws_address)
+            .map_err(|e| anyhow::anyhow!("Failed to listen on {}: {}", ws_address, e))?;
+        println!("Streaming on ws://{}", ws_address);
+        let jobs = job_sender.clone();
+        thread::Builder::new()
+            .name("ws-listener".to_string())
+            .spawn(move || websocket::serve(listener, jobs))?;
+    }
+
+    for request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+        match (request.method(), path.as_str()) {
+            (Method::Options, _) => {
+                let mut response = Response::empty(204);
+                for h in cors_headers() {
+                    response.add_header(h);
+                }
+                let _ = request.respond(response);
+            }
+            (Method::Get, "/health") => respond(
+                request,
+                json_response(
+                    200,
+                    &serde_json::json!({ "status": "ok", "sampleRate": sample_rate, "workers": workers }),
+                ),
+            ),
+            (Method::Get, "/voices") => respond(request, json_response(200, &voices.to_value())),
+            (Method::Post, "/synthesize") => match job_sender.try_send(Job::Http(request)) {
+                Ok(()) => {}
+                Err(TrySendError::Full(Job::Http(request))) => {
+                    let response = error_response(&HttpError::new(503, "Server busy, try again later"))
+                        .with_header(header("Retry-After", "1"));
+                    respond(request, response);
+                }
+                Err(TrySendError::Disconnected(Job::Http(request))) => {
+                    respond(request, error_response(&HttpError::new(500, "No synthesis workers running")));
+                }
+                Err(_) => unreachable!("the rejected job is the one just sent"),
+            },
+            (_, "/health" | "/voices" | "/synthesize") => {
+                respond(request, error_response(&HttpError::new(405, "Method not allowed")))
+            }
+            _ => respond(request, error_response(&HttpError::new(404, "Not found"))),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,375 @@
+// ============================================================================
+// SSML - parses the supported SSML subset into synthesis segments
+// ============================================================================
+//
+// Supported elements: <speak>, <break time|strength>, <prosody rate>,
+// <say-as interpret-as>, <lang xml:lang>, <voice name>, <p>, <s>,
+// <sub alias>. Unknown elements are transparent (their text is spoken).
+
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node};
+
+use crate::helper::is_valid_lang;
+
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Longest pause a single `<break>` may request (seconds)
+const MAX_BREAK: f32 = 10.0;
+
+/// What separates a segment from the one before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SsmlBoundary {
+    /// A change of voice, language or rate, or a `<break>`, within a sentence
+    Inline,
+    /// `<s>`
+    Sentence,
+    /// `<p>`
+    Paragraph,
+}
+
+/// A run of text synthesized with one language, voice and rate
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsmlSegment {
+    pub text: String,
+    pub lang: String,
+    /// `<voice name>`; `None` uses the caller's default style
+    pub voice: Option<String>,
+    /// Multiplier applied to `SynthesisOptions::speed`
+    pub rate: f32,
+    /// Pause before this segment requested by `<break>`; `None` keeps the
+    /// regular pause for `boundary`
+    pub pause_before: Option<f32>,
+    pub boundary: SsmlBoundary,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SsmlDocument {
+    pub segments: Vec<SsmlSegment>,
+    /// Pause requested by `<break>` after the last segment (seconds)
+    pub trailing_pause: f32,
+}
+
+/// Whether `text` should be treated as SSML rather than plain text
+pub fn is_ssml(text: &str) -> bool {
+    text.trim_start().starts_with("<speak")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Scope {
+    lang: String,
+    voice: Option<String>,
+    rate: f32,
+}
+
+struct SegmentBuilder {
+    segments: Vec<SsmlSegment>,
+    text: String,
+    scope: Option<Scope>,
+    pending_pause: Option<f32>,
+    /// Strongest boundary since the last segment
+    pending_boundary: SsmlBoundary,
+}
+
+impl SegmentBuilder {
+    fn push_text(&mut self, text: &str, scope: &Scope) {
+        if self.scope.as_ref() != Some(scope) {
+            self.flush();
+            self.scope = Some(scope.clone());
+        }
+        self.text.push_str(text);
+    }
+
+    fn push_break(&mut self, seconds: f32) {
+        self.flush();
+        *self.pending_pause.get_or_insert(0.0) += seconds;
+    }
+
+    fn push_boundary(&mut self, boundary: SsmlBoundary) {
+        self.flush();
+        self.pending_boundary = self.pending_boundary.max(boundary);
+    }
+
+    fn flush(&mut self) {
+        let text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.text.clear();
+        let scope = match self.scope.take() {
+            Some(scope) if !text.is_empty() => scope,
+            // Keep any pending pause and boundary for the next segment
+            _ => return,
+        };
+        self.segments.push(SsmlSegment {
+            text,
+            lang: scope.lang,
+            voice: scope.voice,
+            rate: scope.rate,
+            pause_before: self.pending_pause.take(),
+            boundary: std::mem::replace(&mut self.pending_boundary, SsmlBoundary::Inline),
+        });
+    }
+}
+
+/// Parse an SSML document. `default_lang` applies until an `xml:lang`
+/// attribute says otherwise.
+pub fn parse_ssml(ssml: &str, default_lang: &str) -> Result<SsmlDocument> {
+    let doc = Document::parse(ssml).context("Invalid SSML")?;
+    let root = doc.root_element();
+    if root.tag_name().name() != "speak" {
+        bail!("SSML root element must be <speak>, found <{}>", root.tag_name().name());
+    }
+
+    let scope = Scope {
+        lang: default_lang.to_string(),
+        voice: None,
+        rate: 1.0,
+    };
+    let mut builder = SegmentBuilder {
+        segments: Vec::new(),
+        text: String::new(),
+        scope: None,
+        pending_pause: None,
+        pending_boundary: SsmlBoundary::Inline,
+    };
+    visit(root, &scope, &mut builder)?;
+    builder.flush();
+
+    Ok(SsmlDocument {
+        segments: builder.segments,
+        trailing_pause: builder.pending_pause.unwrap_or(0.0),
+    })
+}
+
+fn visit(node: Node, scope: &Scope, builder: &mut SegmentBuilder) -> Result<()> {
+    if node.is_text() {
+        builder.push_text(node.text().unwrap_or(""), scope);
+        return Ok(());
+    }
+    if !node.is_element() {
+        return Ok(());
+    }
+
+    let mut scope = scope.clone();
+    if let Some(lang) = node.attribute((XML_NS, "lang")) {
+        scope.lang = parse_lang(lang)?;
+    }
+
+    match node.tag_name().name() {
+        "break" => {
+            builder.push_break(parse_break(node)?);
+            return Ok(());
+        }
+        "sub" => {
+            match node.attribute("alias") {
+                Some(alias) => builder.push_text(alias, &scope),
+                None => visit_children(node, &scope, builder)?,
+            }
+            return Ok(());
+        }
+        "say-as" => {
+            let text: String = node
+                .descendants()
+                .filter(|n| n.is_text())
+                .filter_map(|n| n.text())
+                .collect();
+            let interpret_as = node.attribute("interpret-as").unwrap_or("");
+            builder.push_text(&say_as(&text, interpret_as), &scope);
+            return Ok(());
+        }
+        "prosody" => {
+            if let Some(rate) = node.attribute("rate") {
+                scope.rate *= parse_rate(rate)?;
+            }
+        }
+        "voice" => {
+            if let Some(name) = node.attribute("name") {
+                scope.voice = Some(name.to_string());
+            }
+            if let Some(lang) = node.attribute("language") {
+                scope.lang = parse_lang(lang)?;
+            }
+        }
+        "p" | "s" => {
+            // Paragraphs and sentences always start a new segment
+            let boundary = match node.tag_name().name() {
+                "p" => SsmlBoundary::Paragraph,
+                _ => SsmlBoundary::Sentence,
+            };
+            builder.push_boundary(boundary);
+            visit_children(node, &scope, builder)?;
+            builder.push_boundary(boundary);
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    visit_children(node, &scope, builder)
+}
+
+fn visit_children(node: Node, scope: &Scope, builder: &mut SegmentBuilder) -> Result<()> {
+    for child in node.children() {
+        visit(child, scope, builder)?;
+    }
+    Ok(())
+}
+
+/// "en-US" -> "en"
+fn parse_lang(lang: &str) -> Result<String> {
+    let primary = lang.split(['-', '_']).next().unwrap_or("").to_lowercase();
+    if !is_valid_lang(&primary) {
+        bail!("Unsupported language in SSML: {}", lang);
+    }
+    Ok(primary)
+}
+
+fn parse_break(node: Node) -> Result<f32> {
+    if let Some(time) = node.attribute("time") {
+        let time = time.trim();
+        let seconds = if let Some(ms) = time.strip_suffix("ms") {
+            ms.trim().parse::<f32>().map(|v| v / 1000.0)
+        } else if let Some(s) = time.strip_suffix('s') {
+            s.trim().parse::<f32>()
+        } else {
+            time.parse::<f32>()
+        }
+        .with_context(|| format!("Invalid break time: {}", time))?;
+        if !seconds.is_finite() || seconds < 0.0 {
+            bail!("Invalid break time: {}", time);
+        }
+        return Ok(seconds.min(MAX_BREAK));
+    }
+
+    Ok(match node.attribute("strength").unwrap_or("medium") {
+        "none" => 0.0,
+        "x-weak" => 0.1,
+        "weak" => 0.2,
+        "medium" => 0.4,
+        "strong" => 0.7,
+        "x-strong" => 1.2,
+        other => bail!("Invalid break strength: {}", other),
+    })
+}
+
+fn parse_rate(rate: &str) -> Result<f32> {
+    let rate = rate.trim();
+    let factor = match rate {
+        "x-slow" => 0.6,
+        "slow" => 0.8,
+        "medium" | "default" => 1.0,
+        "fast" => 1.2,
+        "x-fast" => 1.4,
+        _ => {
+            let parsed = if let Some(percent) = rate.strip_suffix('%') {
+                // "+20%" is relative to the current rate, "120%" absolute
+                percent.parse::<f32>().map(|p| {
+                    if percent.starts_with(['+', '-']) { 1.0 + p / 100.0 } else { p / 100.0 }
+                })
+            } else {
+                rate.parse::<f32>()
+            };
+            parsed.with_context(|| format!("Invalid prosody rate: {}", rate))?
+        }
+    };
+    // NaN passes a plain `<= 0.0` check and `clamp`
+    if !factor.is_finite() || factor <= 0.0 {
+        bail!("Invalid prosody rate: {}", rate);
+    }
+    Ok(factor.clamp(0.25, 4.0))
+}
+
+fn say_as(text: &str, interpret_as: &str) -> String {
+    let chars = text.chars().filter(|c| !c.is_whitespace());
+    let spoken: Vec<String> = match interpret_as {
+        "characters" | "spell-out" | "verbatim" => chars.map(String::from).collect(),
+        // Separators such as "-" become pauses so "555-1234" is not read as a range
+        "digits" | "telephone" => chars
+            .map(|c| if c.is_ascii_digit() { c.to_string() } else { ",".to_string() })
+            .collect(),
+        // Numbers, dates and the like are left to the text normalizer
+        _ => return text.to_string(),
+    };
+    spoken.join(" ").replace(" ,", ",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(ssml: &str) -> SsmlDocument {
+        parse_ssml(ssml, "en").unwrap()
+    }
+
+    fn texts(doc: &SsmlDocument) -> Vec<&str> {
+        doc.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn breaks_split_segments_and_add_up() {
+        let doc = parse(r#"<speak>One <break time="250ms"/><break strength="strong"/> two<break time="20s"/></speak>"#);
+        assert_eq!(texts(&doc), ["One", "two"]);
+        assert_eq!(doc.segments[0].pause_before, None);
+        assert_eq!(doc.segments[1].pause_before, Some(0.25 + 0.7));
+        assert_eq!(doc.segments[1].boundary, SsmlBoundary::Inline);
+        assert_eq!(doc.trailing_pause, MAX_BREAK);
+    }
+
+    #[test]
+    fn sentences_and_paragraphs_set_the_boundary() {
+        let doc = parse("<speak><p><s>First.</s><s>Second.</s></p><p>Third.</p></speak>");
+        assert_eq!(texts(&doc), ["First.", "Second.", "Third."]);
+        let boundaries: Vec<_> = doc.segments.iter().map(|s| s.boundary).collect();
+        assert_eq!(boundaries, [SsmlBoundary::Paragraph, SsmlBoundary::Sentence, SsmlBoundary::Paragraph]);
+    }
+
+    #[test]
+    fn prosody_rates_multiply() {
+        let doc = parse(r#"<speak><prosody rate="slow">a<prosody rate="+50%">b</prosody></prosody><prosody rate="150%">c</prosody><prosody rate="10">d</prosody></speak>"#);
+        let rates: Vec<f32> = doc.segments.iter().map(|s| s.rate).collect();
+        assert_eq!(rates, [0.8, 0.8 * 1.5, 1.5, 4.0]);
+    }
+
+    #[test]
+    fn lang_and_voice_nest() {
+        let doc = parse(
+            r#"<speak>Hi <voice name="F1">there <lang xml:lang="ko-KR">안녕</lang> <voice name="M2" language="fr">salut</voice></voice> bye</speak>"#,
+        );
+        let segments: Vec<_> = doc
+            .segments
+            .iter()
+            .map(|s| (s.text.as_str(), s.lang.as_str(), s.voice.as_deref()))
+            .collect();
+        assert_eq!(
+            segments,
+            [
+                ("Hi", "en", None),
+                ("there", "en", Some("F1")),
+                ("안녕", "ko", Some("F1")),
+                ("salut", "fr", Some("M2")),
+                ("bye", "en", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_and_say_as_replace_text() {
+        let doc = parse(r#"<speak><sub alias="World Wide Web">WWW</sub> <say-as interpret-as="telephone">555-12</say-as></speak>"#);
+        assert_eq!(texts(&doc), ["World Wide Web 5 5 5, 1 2"]);
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        for ssml in [
+            "<speak>unclosed",
+            "<voice>not speak</voice>",
+            r#"<speak><lang xml:lang="de">Hallo</lang></speak>"#,
+            r#"<speak><break time="-1s"/></speak>"#,
+            r#"<speak><break time="NaNs"/></speak>"#,
+            r#"<speak><break strength="loud"/></speak>"#,
+            r#"<speak><prosody rate="0%">x</prosody></speak>"#,
+            r#"<speak><prosody rate="NaN">x</prosody></speak>"#,
+            r#"<speak><prosody rate="NaN%">x</prosody></speak>"#,
+            r#"<speak><prosody rate="inf">x</prosody></speak>"#,
+            r#"<speak><prosody rate="quick">x</prosody></speak>"#,
+        ] {
+            assert!(parse_ssml(ssml, "en").is_err(), "{}", ssml);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+// ============================================================================
+// Voice Style Cache - parsed styles kept across synthesize calls
+// ============================================================================
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::error::{EngineError, EngineResult};
+use crate::helper::{load_and_blend_voice_styles, load_voice_style, BlendComponent, Style};
+
+pub const DEFAULT_STYLE_CACHE_CAPACITY: usize = 16;
+
+/// A single style file or a blend recipe
+#[derive(Debug, Clone, PartialEq)]
+enum StyleKey {
+    Single(PathBuf),
+    Blend(Vec<BlendComponent>),
+}
+
+impl StyleKey {
+    /// Accepts a plain path, a JSON array of blend components
+    /// (`[{"path", "weight", "ttlWeight", "dpWeight"}, ...]`) or the legacy
+    /// `path1;path2;alpha` mix
+    fn parse(style_path: &str) -> EngineResult<Self> {
+        let trimmed = style_path.trim_start();
+        if trimmed.starts_with('[') {
+            let components: Vec<BlendComponent> = serde_json::from_str(trimmed).map_err(|e| {
+                EngineError::InvalidArgument(format!("Invalid voice blend JSON: {}", e))
+            })?;
+            if components.is_empty() {
+                return Err(EngineError::InvalidArgument("Voice blend has no voices".to_string()));
+            }
+            return Ok(StyleKey::Blend(components));
+        }
+
+        if !style_path.contains(';') {
+            return Ok(StyleKey::Single(PathBuf::from(style_path)));
+        }
+
+        let parts: Vec<&str> = style_path.split(';').collect();
+        if parts.len() != 3 {
+            return Err(EngineError::InvalidArgument(format!(
+                "Invalid mix format '{}'. Expected: path1;path2;alpha",
+                style_path
+            )));
+        }
+        let alpha = parts[2].trim().parse::<f32>().map_err(|_| {
+            EngineError::InvalidArgument(format!("Invalid mix alpha '{}'", parts[2]))
+        })?;
+        let component = |path: &str, weight: f32| BlendComponent {
+            path: path.to_string(),
+            weight,
+            ttl_weight: None,
+            dp_weight: None,
+        };
+        Ok(StyleKey::Blend(vec![
+            component(parts[0], 1.0 - alpha),
+            component(parts[1], alpha),
+        ]))
+    }
+
+    fn sources(&self) -> Vec<&Path> {
+        match self {
+            StyleKey::Single(path) => vec![path],
+            StyleKey::Blend(components) => components.iter().map(|c| Path::new(&c.path)).collect(),
+        }
+    }
+
+    fn load(&self) -> EngineResult<Style> {
+        match self {
+            StyleKey::Single(path) => {
+                load_voice_style(&[path.to_string_lossy().into_owned()], false)
+            }
+            StyleKey::Blend(components) => load_and_blend_voice_styles(components),
+        }
+        .map_err(EngineError::VoiceStyle)
+    }
+
+    fn modification_times(&self) -> Vec<Option<SystemTime>> {
+        self.sources()
+            .into_iter()
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+struct CachedStyle {
+    key: StyleKey,
+    style: Arc<Style>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+/// Least-recently-used cache of parsed styles. A style is reloaded when any
+/// of its source files changed on disk since it was cached.
+pub struct StyleCache {
+    /// Ordered from least to most recently used
+    entries: Vec<CachedStyle>,
+    capacity: usize,
+}
+
+impl StyleCache {
+    pub fn new(capacity: usize) -> Self {
+        StyleCache {
+            entries: Vec::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Style for `style_path` (a file or a blend recipe), loading it
+    /// on a miss or when its files were modified
+    pub fn get(&mut self, style_path: &str) -> EngineResult<Arc<Style>> {
+        let key = StyleKey::parse(style_path)?;
+        let modified = key.modification_times();
+
+        if let Some(pos) = self.entries.iter().position(|e| e.key == key) {
+            let entry = self.entries.remove(pos);
+            if entry.modified == modified {
+                let style = entry.style.clone();
+                self.entries.push(entry);
+                return Ok(style);
+            }
+            log::info!("Voice style changed on disk, reloading: {}", style_path);
+        }
+
+        let style = Arc::new(key.load()?);
+        if self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+        }
+        self.entries.push(CachedStyle {
+            key,
+            style: style.clone(),
+            modified,
+        });
+        Ok(style)
+    }
+
+    /// Drop the entry for `style_path`. For a single file, blends using that
+    /// file are dropped too. Returns the number of removed entries.
+    pub fn evict(&mut self, style_path: &str) -> EngineResult<usize> {
+        let key = StyleKey::parse(style_path)?;
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            let uses_file = match &key {
+                StyleKey::Single(path) => entry.key.sources().contains(&path.as_path()),
+                StyleKey::Blend(_) => false,
+            };
+            entry.key != key && !uses_file
+        });
+        Ok(before - self.entries.len())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Directory of the first file referenced by `style_path`, used to resolve
+/// SSML `<voice name>` references
+pub fn style_directory(style_path: &str) -> PathBuf {
+    StyleKey::parse(style_path)
+        .ok()
+        .and_then(|key| key.sources().first().and_then(|p| p.parent()).map(Path::to_path_buf))
+        .unwrap_or_default()
+}
@@ -0,0 +1,207 @@
+// ============================================================================
+// Subtitle Export - SRT and WebVTT captions for synthesized audio
+// ============================================================================
+
+use anyhow::Result;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use supertonic_tts::helper::ChunkTiming;
+
+/// Captions longer than this are split on word boundaries
+pub const DEFAULT_MAX_CUE_CHARS: usize = 84;
+
+#[derive(Debug, Clone)]
+pub struct Cue {
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+/// Build caption cues from chunk timings.
+///
+/// Every chunk becomes one cue; chunks longer than `max_chars` are split into
+/// several cues using their word timings. Cue text is taken from `original`
+/// (the text passed to synthesis) so punctuation and numbers read as written.
+pub fn build_cues(original: &str, chunks: &[ChunkTiming], max_chars: usize) -> Vec<Cue> {
+    let original: Vec<char> = original.chars().collect();
+    let mut cues = Vec::new();
+
+    for chunk in chunks {
+        if chunk.text.chars().count() <= max_chars || chunk.words.len() < 2 {
+            cues.push(Cue {
+                start: chunk.start,
+                end: chunk.end,
+                text: collapse_whitespace(&chunk.text),
+            });
+            continue;
+        }
+
+        // Group words into runs of at most max_chars source characters
+        let mut groups: Vec<(usize, usize)> = Vec::new();
+        let mut first = 0;
+        for (i, word) in chunk.words.iter().enumerate().skip(1) {
+            if word.char_end.saturating_sub(chunk.words[first].char_start) > max_chars {
+                groups.push((first, i));
+                first = i;
+            }
+        }
+        groups.push((first, chunk.words.len()));
+
+        for (g, &(first, last)) in groups.iter().enumerate() {
+            // Text runs up to the next group so trailing punctuation is kept
+            let char_start = chunk.words[first].char_start;
+            let char_end = if last < chunk.words.len() {
+                chunk.words[last].char_start
+            } else {
+                chunk.words[last - 1].char_end
+                    + trailing_punctuation(&original, chunk.words[last - 1].char_end)
+            };
+            let text: String = original[char_start.min(original.len())..char_end.min(original.len())]
+                .iter()
+                .collect();
+
+            let start = if g == 0 { chunk.start } else { chunk.words[first].start };
+            let end = if last == chunk.words.len() { chunk.end } else { chunk.words[last - 1].end };
+            cues.push(Cue { start, end, text: collapse_whitespace(&text) });
+        }
+    }
+
+    cues.retain(|cue| !cue.text.is_empty());
+    cues
+}
+
+fn trailing_punctuation(text: &[char], from: usize) -> usize {
+    text[from.min(text.len())..]
+        .iter()
+        .take_while(|c| !c.is_alphanumeric() && !c.is_whitespace())
+        .count()
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn format_timestamp(seconds: f32, fraction_sep: char) -> String {
+    let total_ms = (seconds.max(0.0) * 1000.0).round() as u64;
+    let ms = total_ms % 1000;
+    let s = (total_ms / 1000) % 60;
+    let m = (total_ms / 60_000) % 60;
+    let h = total_ms / 3_600_000;
+    format!("{:02}:{:02}:{:02}{}{:03}", h, m, s, fraction_sep, ms)
+}
+
+pub fn to_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for (i, cue) in cues.iter().enumerate() {
+        let _ = write!(
+            out,
+            "{}\n{} --> {}\n{}\n\n",
+            i + 1,
+            format_timestamp(cue.start, ','),
+            format_timestamp(cue.end, ','),
+            cue.text
+        );
+    }
+    out
+}
+
+pub fn to_vtt(cues: &[Cue]) -> String {
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        let _ = write!(
+            out,
+            "{} --> {}\n{}\n\n",
+            format_timestamp(cue.start, '.'),
+            format_timestamp(cue.end, '.'),
+            // "-->" would end the cue timing line early in some players
+            cue.text.replace("-->", "->")
+        );
+    }
+    out
+}
+
+pub fn write_srt<P: AsRef<Path>>(filename: P, cues: &[Cue]) -> Result<()> {
+    fs::write(filename, to_srt(cues))?;
+    Ok(())
+}
+
+pub fn write_vtt<P: AsRef<Path>>(filename: P, cues: &[Cue]) -> Result<()> {
+    fs::write(filename, to_vtt(cues))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use supertonic_tts::timing::WordTiming;
+
+    fn word(word: &str, char_start: usize, start: f32, end: f32) -> WordTiming {
+        WordTiming {
+            word: word.to_string(),
+            char_start,
+            char_end: char_start + word.chars().count(),
+            start,
+            end,
+        }
+    }
+
+    fn cue(start: f32, end: f32, text: &str) -> Cue {
+        Cue { start, end, text: text.to_string() }
+    }
+
+    #[test]
+    fn timestamps_carry_hours_and_round_milliseconds() {
+        assert_eq!(format_timestamp(0.0, ','), "00:00:00,000");
+        assert_eq!(format_timestamp(1.2345, ','), "00:00:01,235");
+        assert_eq!(format_timestamp(59.9996, '.'), "00:01:00.000");
+        assert_eq!(format_timestamp(3725.5, '.'), "01:02:05.500");
+        assert_eq!(format_timestamp(-1.0, ','), "00:00:00,000");
+    }
+
+    #[test]
+    fn long_chunks_split_on_word_boundaries() {
+        let original = "one two  three four.";
+        let chunk = ChunkTiming {
+            text: original.to_string(),
+            start: 0.5,
+            end: 3.0,
+            words: vec![
+                word("one", 0, 0.5, 0.8),
+                word("two", 4, 0.9, 1.2),
+                word("three", 9, 1.4, 1.9),
+                word("four", 15, 2.0, 2.6),
+            ],
+        };
+
+        let cues = build_cues(original, std::slice::from_ref(&chunk), 8);
+        let texts: Vec<&str> = cues.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["one two", "three", "four."]);
+        // The first and last cue reach the chunk edges, the rest follow words
+        assert_eq!((cues[0].start, cues[0].end), (0.5, 1.2));
+        assert_eq!((cues[1].start, cues[1].end), (1.4, 1.9));
+        assert_eq!((cues[2].start, cues[2].end), (2.0, 3.0));
+
+        // Short chunks stay whole, with their whitespace collapsed
+        let whole = build_cues(original, &[chunk], DEFAULT_MAX_CUE_CHARS);
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].text, "one two three four.");
+    }
+
+    #[test]
+    fn srt_numbers_cues() {
+        let srt = to_srt(&[cue(0.0, 1.5, "Hello."), cue(1.5, 2.25, "Bye.")]);
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,500\nHello.\n\n2\n00:00:01,500 --> 00:00:02,250\nBye.\n\n"
+        );
+    }
+
+    #[test]
+    fn vtt_has_a_header_and_escapes_arrows() {
+        let vtt = to_vtt(&[cue(0.0, 1.0, "a --> b")]);
+        assert_eq!(vtt, "WEBVTT\n\n00:00:00.000 --> 00:00:01.000\na -> b\n\n");
+        assert_eq!(to_vtt(&[]), "WEBVTT\n\n");
+    }
+}
@@ -1,3 +1,5 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 #[allow(unused_imports)]
 use anyhow::Result;
@@ -35,7 +37,7 @@ impl CpuTopology {
             return Self::fallback();
         }
         
-        cores_with_freq.sort_by(|a, b| b.1.cmp(&a.1));
+        cores_with_freq.sort_by_key(|&(_, freq)| std::cmp::Reverse(freq));
         
         let max_freq = cores_with_freq[0].1;
         let num_cores = cores_with_freq.len();
@@ -173,7 +175,6 @@ pub struct ThermalConfig {
     pub cooldown_duration: Duration,
 
     // Core selection strategy
-    #[allow(dead_code)]
     pub use_prime_core: bool,
     pub sustained_mode_cores: CoreSelection,
 }
@@ -183,9 +184,7 @@ pub enum CoreSelection {
     AllPerf,           // Use all performance cores
     SinglePerf,        // Use 1 performance core
     HybridPerfLittle,  // Mix of perf + little
-    #[allow(dead_code)]
     AllLittle,         // Use all little cores
-    #[allow(dead_code)]
     MinimalLittle,     // Use minimal little cores
 }
 
@@ -246,6 +245,15 @@ pub struct UnifiedThermalManager {
     // Performance tracking
     baseline_rtf: Option<f32>,
     recent_rtf: Vec<f32>,
+
+    // Intra-op thread count for the current mode, read by the TTS engine
+    thread_budget: Arc<AtomicUsize>,
+}
+
+impl Default for UnifiedThermalManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl UnifiedThermalManager {
@@ -264,7 +272,7 @@ impl UnifiedThermalManager {
             config.max_high_power_duration
         );
         
-        Self {
+        let manager = Self {
             topology,
             config,
             current_mode: ThermalMode::ColdStart,
@@ -272,7 +280,10 @@ impl UnifiedThermalManager {
             last_mode_switch: Instant::now(),
             baseline_rtf: None,
             recent_rtf: Vec::with_capacity(5),
-        }
+            thread_budget: Arc::new(AtomicUsize::new(0)),
+        };
+        manager.publish_thread_count();
+        manager
     }
     
     pub fn update(&mut self, buffer_seconds: f32, current_rtf: f32) -> ThermalMode {
@@ -315,12 +326,14 @@ impl UnifiedThermalManager {
                 self.high_power_usage_time = Duration::ZERO;
             }
             
+            self.publish_thread_count();
             self.apply_affinity().ok();
         }
         
         new_mode
     }
     
+    #[allow(clippy::if_same_then_else)]
     fn decide_mode(&self, buffer_seconds: f32, rtf_degradation: bool, elapsed: Duration) -> ThermalMode {
         match self.current_mode {
             ThermalMode::ColdStart => {
@@ -449,11 +462,21 @@ impl UnifiedThermalManager {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_thread_count(&self) -> usize {
         self.get_cores_for_mode().len().max(1)
     }
 
+    /// Shared thread count that follows the current mode. Hand it to
+    /// `TextToSpeech::set_thread_budget` so ORT's thread pools match the cores
+    /// the mode allows instead of oversubscribing them.
+    pub fn thread_budget(&self) -> Arc<AtomicUsize> {
+        Arc::clone(&self.thread_budget)
+    }
+
+    fn publish_thread_count(&self) {
+        self.thread_budget.store(self.get_thread_count(), Ordering::Relaxed);
+    }
+
     fn detect_throttling(&self) -> bool {
         if let Some(baseline) = self.baseline_rtf {
             if self.recent_rtf.len() >= 3 {
@@ -469,7 +492,6 @@ impl UnifiedThermalManager {
         self.topology.soc_class
     }
 
-    #[allow(dead_code)]
     pub fn get_current_mode(&self) -> ThermalMode {
         self.current_mode
     }
@@ -0,0 +1,274 @@
+// ============================================================================
+// Word Timing - maps synthesized audio back to words of the input text
+// ============================================================================
+
+use serde::Serialize;
+use unicode_normalization::UnicodeNormalization;
+
+/// A word of the input text and where it is spoken in the output audio
+#[derive(Debug, Clone, Serialize)]
+pub struct WordTiming {
+    pub word: String,
+    /// Character (not byte) range of the word in the original input text
+    pub char_start: usize,
+    pub char_end: usize,
+    /// Seconds from the start of the concatenated output
+    pub start: f32,
+    pub end: f32,
+}
+
+// Relative spoken length used when the duration model does not expose
+// per-token durations
+const LETTER_WEIGHT: f32 = 1.0;
+const SPACE_WEIGHT: f32 = 0.5;
+const CLAUSE_PAUSE_WEIGHT: f32 = 2.0;
+const SENTENCE_PAUSE_WEIGHT: f32 = 3.0;
+const OTHER_WEIGHT: f32 = 0.3;
+
+fn char_weight(c: char) -> f32 {
+    if c.is_alphanumeric() {
+        LETTER_WEIGHT
+    } else if c.is_whitespace() {
+        SPACE_WEIGHT
+    } else {
+        match c {
+            ',' | ';' | ':' => CLAUSE_PAUSE_WEIGHT,
+            '.' | '!' | '?' | '…' => SENTENCE_PAUSE_WEIGHT,
+            _ => OTHER_WEIGHT,
+        }
+    }
+}
+
+/// Strip the `<lang>...</lang>` wrapper added by `preprocess_text`, returning
+/// the char range of the inner text
+fn inner_range(chars: &[char]) -> (usize, usize) {
+    if chars.first() != Some(&'<') {
+        return (0, chars.len());
+    }
+    let open_end = match chars.iter().position(|&c| c == '>') {
+        Some(pos) => pos + 1,
+        None => return (0, chars.len()),
+    };
+    let close_start = chars.iter().rposition(|&c| c == '<').unwrap_or(chars.len());
+    if close_start < open_end {
+        (0, chars.len())
+    } else {
+        (open_end, close_start)
+    }
+}
+
+/// Estimate word spans inside one chunk.
+///
+/// `processed` is the text as fed to the model (after `preprocess_text`) and
+/// `duration` the predicted speech length of the chunk. When the duration
+/// model provides per-token durations (one per char of `processed`) they are
+/// used directly, otherwise the duration is spread proportionally over
+/// character weights. Returned times are relative to the chunk start.
+pub fn estimate_word_spans(
+    processed: &str,
+    duration: f32,
+    token_durations: Option<&[f32]>,
+) -> Vec<(String, f32, f32)> {
+    let chars: Vec<char> = processed.chars().collect();
+    let (inner_start, inner_end) = inner_range(&chars);
+
+    let weights: Vec<f32> = match token_durations {
+        Some(tokens) if tokens.len() == chars.len() => tokens.to_vec(),
+        _ => chars
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| {
+                if i < inner_start || i >= inner_end { 0.0 } else { char_weight(c) }
+            })
+            .collect(),
+    };
+
+    let total: f32 = weights.iter().sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    let scale = duration / total;
+
+    // Start time of every char
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut t = 0.0f32;
+    for &w in &weights {
+        offsets.push(t);
+        t += w * scale;
+    }
+    offsets.push(t);
+
+    let mut spans = Vec::new();
+    let mut i = inner_start;
+    while i < inner_end {
+        if chars[i].is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let word_start = i;
+        while i < inner_end && !chars[i].is_whitespace() {
+            i += 1;
+        }
+
+        // Only letters and digits are spoken; surrounding punctuation is pause
+        let word = &chars[word_start..i];
+        let first = word.iter().position(|c| c.is_alphanumeric());
+        let last = word.iter().rposition(|c| c.is_alphanumeric());
+        if let (Some(first), Some(last)) = (first, last) {
+            let text: String = word[first..=last].iter().collect();
+            spans.push((
+                text,
+                offsets[word_start + first],
+                offsets[word_start + last + 1],
+            ));
+        }
+    }
+
+    spans
+}
+
+/// Locates words of the (normalized) synthesized text in the original input.
+///
+/// The original text is NFKD-decomposed the same way `preprocess_text` does,
+/// keeping track of which original char every decomposed char came from.
+/// Words are searched for in order; words that cannot be found nearby (e.g.
+/// expanded numbers) inherit the span of the original token at the cursor.
+pub struct TextAligner {
+    decomposed: Vec<char>,
+    origin: Vec<usize>,
+    cursor: usize,
+}
+
+const SEARCH_WINDOW: usize = 64;
+
+impl TextAligner {
+    pub fn new(original: &str) -> Self {
+        let mut decomposed = Vec::new();
+        let mut origin = Vec::new();
+        for (idx, c) in original.chars().enumerate() {
+            for d in std::iter::once(c).nfkd() {
+                decomposed.push(d.to_lowercase().next().unwrap_or(d));
+                origin.push(idx);
+            }
+        }
+        TextAligner { decomposed, origin, cursor: 0 }
+    }
+
+    fn is_boundary(&self, pos: usize) -> bool {
+        pos == 0 || !self.decomposed[pos - 1].is_alphanumeric()
+    }
+
+    fn find(&self, needle: &[char]) -> Option<usize> {
+        if needle.is_empty() {
+            return None;
+        }
+        let window_end = (self.cursor + SEARCH_WINDOW.max(needle.len() * 4))
+            .min(self.decomposed.len());
+        (self.cursor..window_end).find(|&pos| {
+            pos + needle.len() <= self.decomposed.len()
+                && self.is_boundary(pos)
+                && self.decomposed[pos..pos + needle.len()] == *needle
+        })
+    }
+
+    /// Char range in the original text for the next synthesized word
+    pub fn locate(&mut self, word: &str) -> (usize, usize) {
+        let needle: Vec<char> = word
+            .chars()
+            .map(|c| c.to_lowercase().next().unwrap_or(c))
+            .collect();
+
+        if let Some(pos) = self.find(&needle) {
+            let end = pos + needle.len();
+            self.cursor = end;
+            return (self.origin[pos], self.origin[end - 1] + 1);
+        }
+
+        // Fall back to the original token under the cursor without consuming
+        // it, so several synthesized words (e.g. "twenty twenty-four") can map
+        // onto the same source token
+        let mut start = self.cursor;
+        while start < self.decomposed.len() && !self.decomposed[start].is_alphanumeric() {
+            start += 1;
+        }
+        if start >= self.decomposed.len() {
+            let end = self.origin.last().map_or(0, |&o| o + 1);
+            return (end, end);
+        }
+        let mut end = start;
+        while end < self.decomposed.len() && !self.decomposed[end].is_whitespace() {
+            end += 1;
+        }
+        while !self.decomposed[end - 1].is_alphanumeric() {
+            end -= 1;
+        }
+        (self.origin[start], self.origin[end - 1] + 1)
+    }
+
+    /// Turn chunk-relative spans into absolute `WordTiming`s
+    pub fn align(&mut self, spans: Vec<(String, f32, f32)>, chunk_start: f32) -> Vec<WordTiming> {
+        spans
+            .into_iter()
+            .map(|(word, start, end)| {
+                let (char_start, char_end) = self.locate(&word);
+                WordTiming {
+                    word,
+                    char_start,
+                    char_end,
+                    start: chunk_start + start,
+                    end: chunk_start + end,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_span(span: &(String, f32, f32), word: &str, start: f32, end: f32) {
+        assert_eq!(span.0, word);
+        assert!((span.1 - start).abs() < 1e-5, "{:?}", span);
+        assert!((span.2 - end).abs() < 1e-5, "{:?}", span);
+    }
+
+    #[test]
+    fn spans_follow_char_weights_inside_the_lang_tags() {
+        // H i ␠ t h e r e . weigh 1 1 0.5 1 1 1 1 1 3
+        let spans = estimate_word_spans("<en>Hi there.</en>", 2.1, None);
+        assert_eq!(spans.len(), 2);
+        let scale = 2.1 / 10.5;
+        assert_span(&spans[0], "Hi", 0.0, 2.0 * scale);
+        assert_span(&spans[1], "there", 2.5 * scale, 7.5 * scale);
+    }
+
+    #[test]
+    fn spans_use_token_durations_when_they_match() {
+        let spans = estimate_word_spans("ab c", 4.0, Some(&[1.0, 1.0, 1.0, 1.0]));
+        assert_span(&spans[0], "ab", 0.0, 2.0);
+        assert_span(&spans[1], "c", 3.0, 4.0);
+
+        // A length mismatch falls back to char weights
+        let spans = estimate_word_spans("ab c", 3.5, Some(&[1.0]));
+        assert_span(&spans[1], "c", 2.5, 3.5);
+    }
+
+    #[test]
+    fn silent_text_has_no_spans() {
+        assert!(estimate_word_spans("<en></en>", 1.0, None).is_empty());
+    }
+
+    #[test]
+    fn aligner_maps_expanded_words_to_the_source_token() {
+        let mut aligner = TextAligner::new("I have 2 Cats.");
+        assert_eq!(aligner.locate("i"), (0, 1));
+        assert_eq!(aligner.locate("have"), (2, 6));
+        assert_eq!(aligner.locate("two"), (7, 8));
+        assert_eq!(aligner.locate("cats"), (9, 13));
+
+        let words = TextAligner::new("Café ok").align(vec![("café".into(), 0.0, 0.5)], 1.0);
+        assert_eq!((words[0].char_start, words[0].char_end), (0, 4));
+        assert_eq!((words[0].start, words[0].end), (1.0, 1.5));
+    }
+}
@@ -0,0 +1,185 @@
+// ============================================================================
+// Voice Registry - enumerate the voice styles in a directory
+// ============================================================================
+//
+// A voice is a style file (`<id>.json` or the binary `<id>.style`; the binary
+// one wins when both exist). Metadata is optional and comes from either a
+// `voices.json` manifest in the same directory:
+//
+//   { "default": "M1", "voices": { "M1": { "name": "Alex", "gender": "male" } } }
+//
+// or a `<id>.meta.json` sidecar next to the style file, which takes precedence.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MANIFEST_FILE: &str = "voices.json";
+const SIDECAR_SUFFIX: &str = ".meta.json";
+/// Default when the manifest names none (the first bundled voice)
+const FALLBACK_DEFAULT_VOICE: &str = "M1";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceMetadata {
+    /// Display name; falls back to the voice id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gender: Option<String>,
+    /// Languages the voice works best with; empty means no preference
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Sample sentence for voice previews
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preview_text: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    voices: HashMap<String, VoiceMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Voice {
+    /// File stem, e.g. `M1`
+    pub id: String,
+    /// Style file to pass to `load_voice_style`
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub metadata: VoiceMetadata,
+}
+
+impl Voice {
+    pub fn display_name(&self) -> &str {
+        self.metadata.name.as_deref().unwrap_or(&self.id)
+    }
+
+    /// True if the voice has no language preference or lists `lang`
+    pub fn supports_language(&self, lang: &str) -> bool {
+        self.metadata.languages.is_empty() || self.metadata.languages.iter().any(|l| l == lang)
+    }
+}
+
+/// Voices found in one directory, sorted by id
+#[derive(Debug, Default)]
+pub struct VoiceRegistry {
+    dir: PathBuf,
+    voices: Vec<Voice>,
+    default_id: Option<String>,
+}
+
+impl VoiceRegistry {
+    /// Scan `dir` for style files and their metadata
+    pub fn scan<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read voice directory: {}", dir.display()))?;
+
+        let manifest_path = dir.join(MANIFEST_FILE);
+        let manifest: Manifest = if manifest_path.exists() {
+            let json = fs::read_to_string(&manifest_path)?;
+            serde_json::from_str(&json)
+                .with_context(|| format!("Invalid voice manifest: {}", manifest_path.display()))?
+        } else {
+            Manifest::default()
+        };
+
+        let mut styles: HashMap<String, PathBuf> = HashMap::new();
+        for entry in entries {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if file_name == MANIFEST_FILE || file_name.ends_with(SIDECAR_SUFFIX) {
+                continue;
+            }
+            let (id, is_binary) = if let Some(id) = file_name.strip_suffix(".style") {
+                (id, true)
+            } else if let Some(id) = file_name.strip_suffix(".json") {
+                (id, false)
+            } else {
+                continue;
+            };
+            if is_binary || !styles.contains_key(id) {
+                styles.insert(id.to_string(), path.clone());
+            }
+        }
+
+        let mut voices = Vec::with_capacity(styles.len());
+        for (id, path) in styles {
+            let sidecar = dir.join(format!("{}{}", id, SIDECAR_SUFFIX));
+            let metadata = if sidecar.exists() {
+                let json = fs::read_to_string(&sidecar)?;
+                serde_json::from_str(&json)
+                    .with_context(|| format!("Invalid voice metadata: {}", sidecar.display()))?
+            } else {
+                manifest.voices.get(&id).cloned().unwrap_or_default()
+            };
+            voices.push(Voice { id, path, metadata });
+        }
+        voices.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(VoiceRegistry {
+            dir: dir.to_path_buf(),
+            voices,
+            default_id: manifest.default,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn voices(&self) -> &[Voice] {
+        &self.voices
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    /// Look up a voice by id (case-insensitive); a trailing `.json` or
+    /// `.style` is ignored so old file-name references keep working
+    pub fn get(&self, id: &str) -> Option<&Voice> {
+        let id = id
+            .strip_suffix(".json")
+            .or_else(|| id.strip_suffix(".style"))
+            .unwrap_or(id);
+        self.voices.iter().find(|v| v.id.eq_ignore_ascii_case(id))
+    }
+
+    /// The manifest's default voice, else `M1`, else the first voice by id
+    pub fn default_voice(&self) -> Option<&Voice> {
+        self.default_id
+            .as_deref()
+            .and_then(|id| self.get(id))
+            .or_else(|| self.get(FALLBACK_DEFAULT_VOICE))
+            .or_else(|| self.voices.first())
+    }
+
+    /// Voices that list `lang` or have no language preference
+    pub fn for_language<'a>(&'a self, lang: &'a str) -> impl Iterator<Item = &'a Voice> + 'a {
+        self.voices.iter().filter(move |v| v.supports_language(lang))
+    }
+
+    /// `{"default": id, "voices": [...]}` for frontends
+    pub fn to_value(&self) -> serde_json::Value {
+        serde_json::json!({
+            "default": self.default_voice().map(|v| &v.id),
+            "voices": self.voices,
+        })
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.to_value())?)
+    }
+}
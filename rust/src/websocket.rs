@@ -0,0 +1,335 @@
+// ============================================================================
+// WebSocket streaming for tts_server
+// ============================================================================
+//
+// Client messages are JSON text frames:
+//
+//   {"type": "synthesize", "id": ..., "text": ..., "lang": ..., "voice": ..., ...}
+//   {"type": "pause"} / {"type": "resume"} / {"type": "cancel"} / {"type": "ping"}
+//
+// `synthesize` takes the fields of an HTTP `/synthesize` request. The server
+// answers with JSON events echoing `id`:
+//
+//   {"type": "start", "sampleRate", "chunks"}
+//   {"type": "chunk", "index", "chunks", "text", "start", "end", "words"}
+//     followed by one binary frame of 16-bit little-endian PCM for that chunk
+//   {"type": "done", "duration"} | {"type": "cancelled"} | {"type": "error", "message"}
+//
+// Pausing stops synthesis before the next chunk (audio already sent stays
+// valid); the request keeps its worker until it is resumed or cancelled, or
+// is cancelled after PAUSE_TIMEOUT.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+use tungstenite::{Error as WsError, Message, WebSocket};
+
+use supertonic_tts::encoder::{to_pcm16_dithered, Dither};
+use supertonic_tts::ssml::is_ssml;
+use crate::{Job, SynthesizeRequest, Worker};
+
+/// How long a connection waits for client messages before forwarding events
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// A stream paused for longer than this is cancelled to free its worker
+const PAUSE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Events a worker may run ahead of its connection (a chunk is two events)
+const EVENT_BUFFER: usize = 8;
+
+/// Pause/cancel state shared between a connection and its worker
+#[derive(Default)]
+pub struct StreamControl {
+    cancelled: AtomicBool,
+    /// Cancelled because the pause ran out
+    timed_out: AtomicBool,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+}
+
+impl StreamControl {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.resumed.notify_all();
+    }
+
+    fn set_paused(&self, paused: bool) {
+        *self.paused.lock().unwrap() = paused;
+        self.resumed.notify_all();
+    }
+
+    /// Block while paused (at most `PAUSE_TIMEOUT`); false once cancelled
+    fn proceed(&self) -> bool {
+        let paused = self.paused.lock().unwrap();
+        let (paused, wait) = self
+            .resumed
+            .wait_timeout_while(paused, PAUSE_TIMEOUT, |paused| {
+                *paused && !self.cancelled.load(Ordering::SeqCst)
+            })
+            .unwrap();
+        if wait.timed_out() && *paused {
+            self.timed_out.store(true, Ordering::SeqCst);
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+        !self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+pub enum StreamEvent {
+    /// A JSON event
+    Event(Value),
+    /// PCM for the chunk announced by the preceding event
+    Audio(Vec<u8>),
+    /// No more events for this request
+    Finished,
+}
+
+pub struct StreamJob {
+    pub request: SynthesizeRequest,
+    /// Client-chosen request id, echoed in every event
+    id: Value,
+    control: Arc<StreamControl>,
+    /// Bounded, so a slow client holds the worker back instead of piling up audio
+    events: SyncSender<StreamEvent>,
+}
+
+impl StreamJob {
+    fn event(&self, mut event: Value) -> bool {
+        event["id"] = self.id.clone();
+        self.events.send(StreamEvent::Event(event)).is_ok()
+    }
+
+    fn cancelled(&self) {
+        let event = if self.control.timed_out.load(Ordering::SeqCst) {
+            json!({ "type": "cancelled", "reason": "Paused for too long" })
+        } else {
+            json!({ "type": "cancelled" })
+        };
+        self.event(event);
+    }
+}
+
+impl Worker {
+    pub fn handle_stream(&mut self, job: StreamJob) {
+        self.run_stream(&job);
+        let _ = job.events.send(StreamEvent::Finished);
+    }
+
+    fn run_stream(&mut self, job: &StreamJob) {
+        let req = &job.request;
+        let (_, style, options) = match self.prepare(req) {
+            Ok(prepared) => prepared,
+            Err(e) => {
+                job.event(json!({ "type": "error", "message": e.message }));
+                return;
+            }
+        };
+        let sample_rate = self.tts.output_sample_rate(&options);
+
+        if is_ssml(&req.text) {
+            // SSML is sent as a single chunk; pause and cancel still take
+            // effect between the chunks it is synthesized in
+            job.event(json!({ "type": "start", "sampleRate": sample_rate, "chunks": 1 }));
+            let mut cancelled = false;
+            let result = self.synthesize_all(req, &style, &options, |_, _, _| {
+                cancelled = !job.control.proceed();
+                !cancelled
+            });
+            if cancelled {
+                job.cancelled();
+                return;
+            }
+            match result {
+                Ok(wav) => {
+                    let duration = wav.len() as f32 / sample_rate as f32;
+                    job.event(json!({
+                        "type": "chunk", "index": 0, "chunks": 1, "text": req.text,
+                        "start": 0.0, "end": duration, "words": [],
+                    }));
+                    let _ = job.events.send(StreamEvent::Audio(to_pcm16_dithered(&wav)));
+                    job.event(json!({ "type": "done", "duration": duration }));
+                }
+                Err(e) => {
+                    job.event(json!({ "type": "error", "message": format!("Synthesis failed: {:#}", e) }));
+                }
+            }
+            return;
+        }
+
+        let mut stream = self.tts.stream(&req.text, &req.lang, &style, &options);
+        let chunks = stream.num_chunks();
+        job.event(json!({ "type": "start", "sampleRate": sample_rate, "chunks": chunks }));
+
+        let mut duration = 0.0;
+        let mut dither = Dither::new();
+        while !stream.is_finished() {
+            if !job.control.proceed() {
+                job.cancelled();
+                return;
+            }
+            let frame = match stream.next() {
+                Some(Ok(frame)) => frame,
+                Some(Err(e)) => {
+                    job.event(json!({ "type": "error", "message": format!("Synthesis failed: {:#}", e) }));
+                    return;
+                }
+                None => break,
+            };
+            duration = frame.end();
+            let sent = job.event(json!({
+                "type": "chunk",
+                "index": frame.chunk_index,
+                "chunks": frame.num_chunks,
+                "text": frame.text,
+                "start": frame.start,
+                "end": frame.end(),
+                "words": frame.words,
+            })) && job.events.send(StreamEvent::Audio(dither.pcm16(&frame.samples))).is_ok();
+            if !sent {
+                // Connection is gone
+                return;
+            }
+        }
+        job.event(json!({ "type": "done", "duration": duration }));
+    }
+}
+
+/// Accept WebSocket clients on `listener`, one thread per connection
+pub fn serve(listener: TcpListener, jobs: SyncSender<Job>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("WebSocket accept failed: {}", e);
+                continue;
+            }
+        };
+        let jobs = jobs.clone();
+        thread::spawn(move || match tungstenite::accept(stream) {
+            Ok(socket) => {
+                if let Err(e) = run_connection(socket, jobs) {
+                    eprintln!("WebSocket connection ended: {:#}", e);
+                }
+            }
+            Err(e) => eprintln!("WebSocket handshake failed: {}", e),
+        });
+    }
+}
+
+/// The request running on this connection
+struct ActiveStream {
+    control: Arc<StreamControl>,
+    events: Receiver<StreamEvent>,
+}
+
+fn send_json(socket: &mut WebSocket<TcpStream>, value: Value) -> Result<()> {
+    socket.send(Message::Text(value.to_string()))?;
+    Ok(())
+}
+
+fn run_connection(mut socket: WebSocket<TcpStream>, jobs: SyncSender<Job>) -> Result<()> {
+    socket.get_mut().set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut active: Option<ActiveStream> = None;
+    let result = handle_messages(&mut socket, &jobs, &mut active);
+
+    // Free the worker if the client left mid-stream (a paused request would
+    // otherwise hold it forever)
+    if let Some(stream) = active {
+        stream.control.cancel();
+    }
+    result
+}
+
+fn handle_messages(
+    socket: &mut WebSocket<TcpStream>,
+    jobs: &SyncSender<Job>,
+    active: &mut Option<ActiveStream>,
+) -> Result<()> {
+    loop {
+        // Forward whatever the worker has produced
+        if let Some(stream) = active.as_ref() {
+            let mut finished = false;
+            while let Ok(event) = stream.events.try_recv() {
+                match event {
+                    StreamEvent::Event(value) => send_json(socket, value)?,
+                    StreamEvent::Audio(pcm) => socket.send(Message::Binary(pcm))?,
+                    StreamEvent::Finished => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            if finished {
+                *active = None;
+            }
+        }
+
+        let message = match socket.read() {
+            Ok(message) => message,
+            Err(WsError::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+            Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => return Ok(()),
+            _ => continue,
+        };
+        let message: Value = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                send_json(socket, json!({ "type": "error", "message": format!("Invalid message: {}", e) }))?;
+                continue;
+            }
+        };
+
+        let id = message.get("id").cloned().unwrap_or(Value::Null);
+        match message.get("type").and_then(Value::as_str).unwrap_or("") {
+            "synthesize" if active.is_some() => send_json(
+                socket,
+                json!({ "type": "error", "id": id, "message": "A synthesis is already running on this connection" }),
+            )?,
+            "synthesize" => {
+                let request: SynthesizeRequest = match serde_json::from_value(message) {
+                    Ok(request) => request,
+                    Err(e) => {
+                        send_json(socket, json!({ "type": "error", "id": id, "message": format!("Invalid request: {}", e) }))?;
+                        continue;
+                    }
+                };
+                let control = Arc::new(StreamControl::default());
+                let (events_sender, events) = mpsc::sync_channel(EVENT_BUFFER);
+                let job = StreamJob { request, id: id.clone(), control: Arc::clone(&control), events: events_sender };
+                match jobs.try_send(Job::Stream(job)) {
+                    Ok(()) => *active = Some(ActiveStream { control, events }),
+                    Err(TrySendError::Full(_)) => send_json(
+                        socket,
+                        json!({ "type": "error", "id": id, "message": "Server busy, try again later" }),
+                    )?,
+                    Err(TrySendError::Disconnected(_)) => send_json(
+                        socket,
+                        json!({ "type": "error", "id": id, "message": "No synthesis workers running" }),
+                    )?,
+                }
+            }
+            "pause" | "resume" | "cancel" => {
+                if let Some(stream) = active.as_ref() {
+                    match message["type"].as_str() {
+                        Some("pause") => stream.control.set_paused(true),
+                        Some("resume") => stream.control.set_paused(false),
+                        _ => stream.control.cancel(),
+                    }
+                }
+            }
+            "ping" => send_json(socket, json!({ "type": "pong", "id": id }))?,
+            other => send_json(
+                socket,
+                json!({ "type": "error", "id": id, "message": format!("Unknown message type '{}'", other) }),
+            )?,
+        }
+    }
+}